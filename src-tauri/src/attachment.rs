@@ -0,0 +1,263 @@
+use crate::index::IndexMeta;
+use crate::sync::lock_repo;
+use crate::{
+    add_entry, commit_and_push, decrypt_with_answer, find_entry, get_gpg_email,
+    verify_security_question,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tauri::command;
+
+pub const FILE_FORMAT: &str = ".file";
+
+// 文件会进入 git 历史，限制单个文件大小
+pub const MAX_FILE_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Serialize, Debug)]
+pub struct FileSecret {
+    file_name: String,
+    mime: String,
+    size: u64,
+    content_base64: Option<String>,
+    output_path: Option<String>,
+}
+
+// 内容可以来自本地路径，也可以由前端以 base64 传入
+#[derive(Deserialize, Debug)]
+pub struct FileInput {
+    path: Option<String>,
+    content_base64: Option<String>,
+    file_name: Option<String>,
+    mime: Option<String>,
+}
+
+#[command]
+pub async fn add_file_secret(
+    app: String,
+    desc: String,
+    file: FileInput,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<(), String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+    let (data, file_name, mime) = read_input(file)?;
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let meta = file_meta(&file_name, &mime, data.len() as u64);
+    let file_number = add_entry(&email, &app, &desc, FILE_FORMAT, &data, meta, &answer)?;
+    commit_and_push(&format!("add: {}.gpg", file_number), &push_to_cloud)
+}
+
+// 同时给出路径和 base64 时以路径为准，返回内容、文件名和 MIME 类型
+fn read_input(file: FileInput) -> Result<(Vec<u8>, String, String), String> {
+    let (data, default_name) = match (&file.path, &file.content_base64) {
+        (Some(path), _) => {
+            let size = fs::metadata(path)
+                .map_err(|e| format!("Failed to read {}: {}", path, e))?
+                .len();
+            check_size(size as usize)?;
+            let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            let name = Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (data, name)
+        }
+        (None, Some(content)) => {
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(content.trim())
+                .map_err(|e| format!("Invalid base64 content: {}", e))?;
+            (data, String::new())
+        }
+        (None, None) => return Err("Either path or content is required".to_string()),
+    };
+    check_size(data.len())?;
+
+    let file_name = file.file_name.unwrap_or(default_name);
+    if file_name.is_empty() {
+        return Err("File name is required".to_string());
+    }
+    let mime = file
+        .mime
+        .unwrap_or_else(|| guess_mime(&file_name).to_string());
+    Ok((data, file_name, mime))
+}
+
+// 指定 output_path 时写入文件，否则以 base64 返回
+#[command]
+pub async fn decrypt_file_secret(
+    id: String,
+    answer: String,
+    output_path: Option<String>,
+) -> Result<FileSecret, String> {
+    let item = find_entry(&id)?;
+    let data = decrypt_with_answer(&id, &answer)?;
+    let file_name = item.meta.file_name.unwrap_or_else(|| format!("{}.bin", id));
+    let mime = item
+        .meta
+        .mime
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    let mut secret = FileSecret {
+        file_name,
+        mime,
+        size: data.len() as u64,
+        content_base64: None,
+        output_path: None,
+    };
+    match output_path {
+        Some(path) => {
+            write_new_file(&path, &data)?;
+            secret.output_path = Some(path);
+        }
+        None => {
+            secret.content_base64 = Some(base64::engine::general_purpose::STANDARD.encode(&data));
+        }
+    }
+    Ok(secret)
+}
+
+// create_new 在打开时原子地检查文件是否存在，不会覆盖已有文件
+fn write_new_file(path: &str, data: &[u8]) -> Result<(), String> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => format!("File {} already exists", path),
+            _ => format!("Failed to write {}: {}", path, e),
+        })?;
+    file.write_all(data)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+pub fn file_meta(file_name: &str, mime: &str, size: u64) -> IndexMeta {
+    IndexMeta {
+        kind: "file".to_string(),
+        file_name: Some(file_name.to_string()),
+        mime: Some(mime.to_string()),
+        size: Some(size),
+        ..Default::default()
+    }
+}
+
+pub fn check_size(size: usize) -> Result<(), String> {
+    if size > MAX_FILE_SIZE {
+        return Err(format!(
+            "File is too large: {} bytes, the limit is {} bytes",
+            size, MAX_FILE_SIZE
+        ));
+    }
+    Ok(())
+}
+
+pub fn guess_mime(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" => "text/plain",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "p12" | "pfx" => "application/x-pkcs12",
+        "pem" | "crt" | "cer" => "application/x-pem-file",
+        "jks" | "keystore" => "application/x-java-keystore",
+        "zip" => "application/zip",
+        "gpg" | "asc" => "application/pgp-encrypted",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("safesecrets-{}-{}", std::process::id(), name))
+    }
+
+    fn input(path: Option<&str>, content: Option<&str>, file_name: Option<&str>) -> FileInput {
+        FileInput {
+            path: path.map(str::to_string),
+            content_base64: content.map(str::to_string),
+            file_name: file_name.map(str::to_string),
+            mime: None,
+        }
+    }
+
+    #[test]
+    fn limits_file_size() {
+        assert!(check_size(MAX_FILE_SIZE).is_ok());
+        assert_eq!(
+            check_size(MAX_FILE_SIZE + 1).unwrap_err(),
+            "File is too large: 10485761 bytes, the limit is 10485760 bytes"
+        );
+
+        let content =
+            base64::engine::general_purpose::STANDARD.encode(vec![0u8; MAX_FILE_SIZE + 1]);
+        let error = read_input(input(None, Some(&content), Some("big.bin"))).unwrap_err();
+        assert!(error.starts_with("File is too large"));
+
+        let path = temp_path("big.bin");
+        fs::write(&path, vec![0u8; MAX_FILE_SIZE + 1]).unwrap();
+        let error = read_input(input(path.to_str(), None, None)).unwrap_err();
+        assert!(error.starts_with("File is too large"));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn reads_path_or_base64() {
+        let path = temp_path("cert.pem");
+        fs::write(&path, b"from path").unwrap();
+        // 路径优先于 base64，文件名取自路径
+        let (data, name, mime) =
+            read_input(input(path.to_str(), Some("aWdub3JlZA=="), None)).unwrap();
+        assert_eq!(data, b"from path");
+        assert!(name.ends_with("cert.pem"));
+        assert_eq!(mime, "application/x-pem-file");
+        let _ = fs::remove_file(&path);
+
+        let (data, name, mime) =
+            read_input(input(None, Some(" aGVsbG8=\n"), Some("hello.txt"))).unwrap();
+        assert_eq!(data, b"hello");
+        assert_eq!(name, "hello.txt");
+        assert_eq!(mime, "text/plain");
+
+        assert_eq!(
+            read_input(input(None, Some("aGVsbG8="), None)).unwrap_err(),
+            "File name is required"
+        );
+        assert!(read_input(input(None, Some("not base64!"), Some("a")))
+            .unwrap_err()
+            .starts_with("Invalid base64 content"));
+        assert_eq!(
+            read_input(input(None, None, Some("a"))).unwrap_err(),
+            "Either path or content is required"
+        );
+    }
+
+    #[test]
+    fn refuses_to_overwrite() {
+        let path = temp_path("out.bin");
+        let path_str = path.to_str().unwrap();
+        let _ = fs::remove_file(&path);
+        write_new_file(path_str, b"first").unwrap();
+        assert_eq!(
+            write_new_file(path_str, b"second").unwrap_err(),
+            format!("File {} already exists", path_str)
+        );
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,581 @@
+use crate::index::ListItem;
+use crate::{
+    decrypt_bytes, get_index_lines, git_commit, local_state_dir, run_git, split_index_lines,
+    write_index,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tiny_keccak::Hasher;
+
+pub mod s3;
+pub mod webdav;
+
+const INDEX_FILE: &str = "000.gpg";
+
+pub struct RemoteObject {
+    pub name: String,
+    pub etag: String,
+}
+
+pub enum UploadResult {
+    Stored(String),
+    Conflict,
+}
+
+// 非 git 的同步后端，只同步仓库根目录下的 *.gpg 文件
+pub trait SyncBackend {
+    fn list(&self) -> Result<Vec<RemoteObject>, String>;
+    fn download(&self, name: &str) -> Result<(Vec<u8>, String), String>;
+    // expected_etag 为 None 时要求远端不存在该文件
+    fn upload(
+        &self,
+        name: &str,
+        data: &[u8],
+        expected_etag: Option<&str>,
+    ) -> Result<UploadResult, String>;
+    fn delete(&self, name: &str, expected_etag: &str) -> Result<(), String>;
+}
+
+// 上次同步成功时本地内容的哈希和远端的 ETag
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SyncedFile {
+    etag: String,
+    local_hash: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct BackendSyncReport {
+    pub uploaded: Vec<String>,
+    pub downloaded: Vec<String>,
+    pub deleted_local: Vec<String>,
+    pub deleted_remote: Vec<String>,
+    pub renumbered: Vec<String>,
+    pub merged_index: bool,
+    pub conflicts: Vec<String>,
+}
+
+// 冲突时由用户选择保留哪一边
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Skip,
+    Upload,
+    DeleteRemote,
+    Download,
+    DeleteLocal,
+    Compare,
+    Forget,
+    Conflict,
+}
+
+// 以上次同步时的记录为基准，判断本地和远端各自是否有变化
+fn decide(
+    synced: Option<&SyncedFile>,
+    local_hash: Option<&String>,
+    remote_etag: Option<&String>,
+) -> Action {
+    let local_changed = local_hash != synced.map(|s| &s.local_hash);
+    let remote_changed = remote_etag != synced.map(|s| &s.etag);
+    match (local_changed, remote_changed) {
+        (false, false) => Action::Skip,
+        (true, false) if local_hash.is_some() => Action::Upload,
+        (true, false) => Action::DeleteRemote,
+        (false, true) if remote_etag.is_some() => Action::Download,
+        (false, true) => Action::DeleteLocal,
+        (true, true) => match (local_hash, remote_etag) {
+            (Some(_), Some(_)) => Action::Compare,
+            (None, None) => Action::Forget,
+            _ => Action::Conflict,
+        },
+    }
+}
+
+pub fn sync_backend(
+    name: &str,
+    backend: &dyn SyncBackend,
+    resolution: Option<Resolution>,
+) -> Result<BackendSyncReport, String> {
+    let mut manifest = read_manifest(name);
+    let mut remote: HashMap<String, String> = backend
+        .list()?
+        .into_iter()
+        .filter(|o| is_vault_file(&o.name))
+        .map(|o| (o.name, o.etag))
+        .collect();
+    let mut local = local_vault_files()?;
+
+    let mut report = BackendSyncReport::default();
+    let index_action = decide(
+        manifest.get(INDEX_FILE),
+        local.get(INDEX_FILE),
+        remote.get(INDEX_FILE),
+    );
+    if index_action == Action::Compare && resolution.is_none() {
+        merge_remote_index(name, backend, &mut manifest, &mut remote, &mut report)?;
+        local = local_vault_files()?;
+    }
+
+    let mut names: BTreeSet<String> = BTreeSet::new();
+    names.extend(remote.keys().cloned());
+    names.extend(local.keys().cloned());
+    names.extend(manifest.keys().cloned());
+
+    for file in names {
+        let local_hash = local.get(&file);
+        let remote_etag = remote.get(&file);
+        let mut action = decide(manifest.get(&file), local_hash, remote_etag);
+
+        // 两边都被修改过，内容相同时直接认为已同步
+        if action == Action::Compare {
+            let (data, etag) = backend.download(&file)?;
+            let hash = hash_bytes(&data);
+            if local_hash == Some(&hash) {
+                manifest.insert(
+                    file.clone(),
+                    SyncedFile {
+                        etag,
+                        local_hash: hash,
+                    },
+                );
+                continue;
+            }
+            action = Action::Conflict;
+        }
+        if action == Action::Conflict {
+            action = match resolution {
+                None => {
+                    report.conflicts.push(file);
+                    continue;
+                }
+                Some(Resolution::Local) if local_hash.is_some() => Action::Upload,
+                Some(Resolution::Local) => Action::DeleteRemote,
+                Some(Resolution::Remote) if remote_etag.is_some() => Action::Download,
+                Some(Resolution::Remote) => Action::DeleteLocal,
+            };
+        }
+
+        match action {
+            Action::Upload => {
+                let data =
+                    fs::read(&file).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+                match backend.upload(&file, &data, remote_etag.map(|s| s.as_str()))? {
+                    UploadResult::Stored(etag) => {
+                        manifest.insert(
+                            file.clone(),
+                            SyncedFile {
+                                etag,
+                                local_hash: hash_bytes(&data),
+                            },
+                        );
+                        report.uploaded.push(file);
+                    }
+                    UploadResult::Conflict => report.conflicts.push(file),
+                }
+            }
+            Action::DeleteRemote => {
+                if let Some(etag) = remote_etag {
+                    backend.delete(&file, etag)?;
+                }
+                manifest.remove(&file);
+                report.deleted_remote.push(file);
+            }
+            Action::Download => {
+                let (data, etag) = backend.download(&file)?;
+                fs::write(&file, &data).map_err(|e| format!("Failed to write {}: {}", file, e))?;
+                manifest.insert(
+                    file.clone(),
+                    SyncedFile {
+                        etag,
+                        local_hash: hash_bytes(&data),
+                    },
+                );
+                report.downloaded.push(file);
+            }
+            Action::DeleteLocal => {
+                if local_hash.is_some() {
+                    fs::remove_file(&file)
+                        .map_err(|e| format!("Failed to remove {}: {}", file, e))?;
+                }
+                manifest.remove(&file);
+                report.deleted_local.push(file);
+            }
+            Action::Forget => {
+                manifest.remove(&file);
+            }
+            Action::Skip | Action::Compare | Action::Conflict => {}
+        }
+    }
+
+    write_manifest(name, &manifest)?;
+    // 索引同步成功后保存一份作为下次合并的基准
+    if !report.conflicts.iter().any(|f| f == INDEX_FILE) && Path::new(INDEX_FILE).exists() {
+        fs::copy(INDEX_FILE, base_index_path(name)?)
+            .map_err(|e| format!("Failed to save index of {}: {}", name, e))?;
+    }
+
+    // ETag 变化但内容相同时工作区没有改动，不需要提交
+    if !run_git(&["status", "--porcelain"])?.trim().is_empty() {
+        git_commit(&format!("sync: {}", name))?;
+    }
+    if !report.conflicts.is_empty() {
+        return Err(format!(
+            "Conflicts with {}: {}",
+            name,
+            report.conflicts.join(", ")
+        ));
+    }
+    Ok(report)
+}
+
+// 索引每次加密的结果都不同，两边都有改动时解密后按编号三方合并；
+// 同一条目两边改得不一样时不做合并，留给用户选择保留哪一边
+fn merge_remote_index(
+    name: &str,
+    backend: &dyn SyncBackend,
+    manifest: &mut HashMap<String, SyncedFile>,
+    remote: &mut HashMap<String, String>,
+    report: &mut BackendSyncReport,
+) -> Result<(), String> {
+    let (data, etag) = backend.download(INDEX_FILE)?;
+    let remote_lines = split_index_lines(&decrypt_bytes(&data)?);
+    let base_lines = match fs::read(base_index_path(name)?) {
+        Ok(data) => split_index_lines(&decrypt_bytes(&data)?),
+        Err(_) => vec![],
+    };
+    let merge = merge_index_lines(&base_lines, &get_index_lines()?, &remote_lines);
+    if !merge.conflicts.is_empty() {
+        log::warn!(
+            "Index of {} has conflicting entries: {}",
+            name,
+            merge.conflicts.join(", ")
+        );
+        return Ok(());
+    }
+
+    for (from, to) in &merge.renamed {
+        let from_file = format!("{}.gpg", from);
+        if Path::new(&from_file).exists() {
+            fs::rename(&from_file, format!("{}.gpg", to))
+                .map_err(|e| format!("Failed to renumber {}: {}", from_file, e))?;
+        }
+        manifest.remove(&from_file);
+        report.renumbered.push(format!("{} -> {}", from, to));
+    }
+    write_index(&vault_email()?, &merge.lines)?;
+
+    // 合并结果视为本地修改，随后以远端当前的 ETag 为条件上传
+    remote.insert(INDEX_FILE.to_string(), etag.clone());
+    manifest.insert(
+        INDEX_FILE.to_string(),
+        SyncedFile {
+            etag,
+            local_hash: String::new(),
+        },
+    );
+    report.merged_index = true;
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct IndexMerge {
+    lines: Vec<String>,
+    renamed: Vec<(String, String)>,
+    conflicts: Vec<String>,
+}
+
+// 两边在同一编号上各自新增了不同的条目时，本地的条目改用新编号
+fn merge_index_lines(base: &[String], local: &[String], remote: &[String]) -> IndexMerge {
+    let by_id = |lines: &[String]| -> BTreeMap<String, String> {
+        lines
+            .iter()
+            .map(|line| (ListItem::parse(line).id, line.clone()))
+            .collect()
+    };
+    let base = by_id(base);
+    let mut local = by_id(local);
+    let remote = by_id(remote);
+    let mut merge = IndexMerge::default();
+
+    let mut next = base
+        .keys()
+        .chain(local.keys())
+        .chain(remote.keys())
+        .filter_map(|id| id.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let collided: Vec<String> = local
+        .iter()
+        .filter(|(id, line)| !base.contains_key(*id) && remote.get(*id).is_some_and(|r| r != *line))
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in collided {
+        let Some(line) = local.remove(&id) else {
+            continue;
+        };
+        let mut item = ListItem::parse(&line);
+        item.id = format!("{:03}", next);
+        next += 1;
+        merge.renamed.push((id, item.id.clone()));
+        local.insert(item.id.clone(), item.to_line());
+    }
+
+    let ids: BTreeSet<&String> = base
+        .keys()
+        .chain(local.keys())
+        .chain(remote.keys())
+        .collect();
+    for id in ids {
+        let (base_line, local_line, remote_line) = (base.get(id), local.get(id), remote.get(id));
+        let line = if local_line == remote_line || remote_line == base_line {
+            local_line
+        } else if local_line == base_line {
+            remote_line
+        } else {
+            merge.conflicts.push(id.clone());
+            local_line
+        };
+        if let Some(line) = line {
+            merge.lines.push(line.clone());
+        }
+    }
+    merge
+        .lines
+        .sort_by_key(|line| ListItem::parse(line).id.parse::<u32>().unwrap_or(0));
+    merge
+}
+
+// 同步在后台线程中进行，不能调用异步的 get_gpg_email
+fn vault_email() -> Result<String, String> {
+    let data = fs::read("./email.gpg").map_err(|e| format!("Failed to read email.gpg: {}", e))?;
+    Ok(String::from_utf8_lossy(&decrypt_bytes(&data)?).to_string())
+}
+
+pub fn secret_path(name: &str) -> Result<PathBuf, String> {
+    Ok(local_state_dir()?.join(format!("{}.secret.gpg", name)))
+}
+
+pub fn read_secret(name: &str) -> Result<String, String> {
+    let path = secret_path(name)?;
+    let data = fs::read(&path).map_err(|e| format!("Failed to read secret of {}: {}", name, e))?;
+    Ok(String::from_utf8_lossy(&decrypt_bytes(&data)?).to_string())
+}
+
+fn is_vault_file(name: &str) -> bool {
+    name.ends_with(".gpg") && !name.contains('/')
+}
+
+fn local_vault_files() -> Result<HashMap<String, String>, String> {
+    let mut files = HashMap::new();
+    let entries = fs::read_dir("./").map_err(|e| format!("Failed to read vault dir: {}", e))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_vault_file(&name) || !entry.path().is_file() {
+            continue;
+        }
+        let data = fs::read(entry.path()).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        files.insert(name, hash_bytes(&data));
+    }
+    Ok(files)
+}
+
+fn hash_bytes(data: &[u8]) -> String {
+    let mut hasher = tiny_keccak::Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(data);
+    hasher.finalize(&mut output);
+    hex::encode(output)
+}
+
+fn manifest_path(name: &str) -> Result<PathBuf, String> {
+    Ok(local_state_dir()?.join(format!("backend-{}.json", name)))
+}
+
+// 上次同步成功时的索引，仍是加密的
+fn base_index_path(name: &str) -> Result<PathBuf, String> {
+    Ok(local_state_dir()?.join(format!("backend-{}.000.gpg", name)))
+}
+
+fn read_manifest(name: &str) -> HashMap<String, SyncedFile> {
+    manifest_path(name)
+        .ok()
+        .filter(|path| Path::new(path).exists())
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_manifest(name: &str, manifest: &HashMap<String, SyncedFile>) -> Result<(), String> {
+    let data = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    fs::write(manifest_path(name)?, data)
+        .map_err(|e| format!("Failed to write sync manifest of {}: {}", name, e))
+}
+
+pub fn remove_local_state(name: &str) {
+    if let Ok(path) = manifest_path(name) {
+        let _ = fs::remove_file(path);
+    }
+    if let Ok(path) = base_index_path(name) {
+        let _ = fs::remove_file(path);
+    }
+    if let Ok(path) = secret_path(name) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn http_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(60))
+        .build()
+}
+
+pub fn read_body(response: ureq::Response) -> Result<Vec<u8>, String> {
+    let mut data = vec![];
+    std::io::Read::read_to_end(&mut response.into_reader(), &mut data)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    Ok(data)
+}
+
+pub fn http_error(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, response) => format!(
+            "Error result for http request: {} {}",
+            code,
+            response.into_string().unwrap_or_default()
+        ),
+        ureq::Error::Transport(t) => format!("Failed to send http request: {}", t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synced(etag: &str, hash: &str) -> SyncedFile {
+        SyncedFile {
+            etag: etag.to_string(),
+            local_hash: hash.to_string(),
+        }
+    }
+
+    fn s(value: &str) -> String {
+        value.to_string()
+    }
+
+    #[test]
+    fn decides_from_manifest() {
+        let base = synced("e1", "h1");
+        let cases = [
+            // 上次同步后两边都没变
+            (Some(&base), Some(s("h1")), Some(s("e1")), Action::Skip),
+            // 只有本地修改、新增或删除
+            (Some(&base), Some(s("h2")), Some(s("e1")), Action::Upload),
+            (None, Some(s("h1")), None, Action::Upload),
+            (Some(&base), None, Some(s("e1")), Action::DeleteRemote),
+            // 只有远端修改、新增或删除
+            (Some(&base), Some(s("h1")), Some(s("e2")), Action::Download),
+            (None, None, Some(s("e1")), Action::Download),
+            (Some(&base), Some(s("h1")), None, Action::DeleteLocal),
+            // 两边都变了
+            (Some(&base), Some(s("h2")), Some(s("e2")), Action::Compare),
+            (None, Some(s("h1")), Some(s("e1")), Action::Compare),
+            (Some(&base), None, None, Action::Forget),
+            (Some(&base), Some(s("h2")), None, Action::Conflict),
+            (Some(&base), None, Some(s("e2")), Action::Conflict),
+            (None, None, None, Action::Skip),
+        ];
+        for (synced, local, remote, expected) in cases {
+            assert_eq!(
+                decide(synced, local.as_ref(), remote.as_ref()),
+                expected,
+                "synced={:?} local={:?} remote={:?}",
+                synced,
+                local,
+                remote
+            );
+        }
+    }
+
+    fn lines(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn merges_independent_changes() {
+        let base = lines(&[
+            "001.github.token.txt",
+            "002.aws.key.txt",
+            "003.gitlab.token.txt",
+        ]);
+        let local = lines(&[
+            "001.github.token.txt\t{\"tags\":[\"work\"]}",
+            "002.aws.key.txt",
+        ]);
+        let remote = lines(&[
+            "001.github.token.txt",
+            "002.aws.key.txt\t{\"folder\":\"cloud\"}",
+            "003.gitlab.token.txt",
+        ]);
+        let merge = merge_index_lines(&base, &local, &remote);
+        assert_eq!(
+            merge.lines,
+            lines(&[
+                "001.github.token.txt\t{\"tags\":[\"work\"]}",
+                "002.aws.key.txt\t{\"folder\":\"cloud\"}",
+            ])
+        );
+        assert!(merge.renamed.is_empty());
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn renumbers_entries_added_on_both_sides() {
+        let base = lines(&["001.github.token.txt"]);
+        let local = lines(&[
+            "001.github.token.txt",
+            "002.local.one.txt",
+            "003.local.two.txt",
+        ]);
+        let remote = lines(&["001.github.token.txt", "002.remote.one.txt"]);
+        let merge = merge_index_lines(&base, &local, &remote);
+        assert_eq!(
+            merge.lines,
+            lines(&[
+                "001.github.token.txt",
+                "002.remote.one.txt",
+                "003.local.two.txt",
+                "004.local.one.txt",
+            ])
+        );
+        assert_eq!(merge.renamed, vec![(s("002"), s("004"))]);
+        assert!(merge.conflicts.is_empty());
+    }
+
+    #[test]
+    fn keeps_identical_additions() {
+        let local = lines(&["001.github.token.txt"]);
+        let merge = merge_index_lines(&[], &local, &local);
+        assert_eq!(merge.lines, local);
+        assert!(merge.renamed.is_empty());
+    }
+
+    #[test]
+    fn reports_entries_changed_on_both_sides() {
+        let base = lines(&["001.github.token.txt", "002.aws.key.txt"]);
+        let local = lines(&["001.github.token.txt\t{\"tags\":[\"a\"]}"]);
+        let remote = lines(&[
+            "001.github.token.txt\t{\"tags\":[\"b\"]}",
+            "002.aws.key.txt",
+        ]);
+        let merge = merge_index_lines(&base, &local, &remote);
+        assert_eq!(merge.conflicts, vec![s("001")]);
+    }
+}
@@ -0,0 +1,236 @@
+use super::{http_agent, http_error, read_body, RemoteObject, SyncBackend, UploadResult};
+use base64::Engine;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:getetag/></d:prop></d:propfind>"#;
+
+pub struct WebDavBackend {
+    base_url: String,
+    authorization: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl WebDavBackend {
+    pub fn new(base_url: &str, username: Option<&str>, password: Option<&str>) -> Self {
+        let authorization = username.map(|user| {
+            let credentials = format!("{}:{}", user, password.unwrap_or(""));
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            )
+        });
+        WebDavBackend {
+            base_url: format!("{}/", base_url.trim_end_matches('/')),
+            authorization,
+            agent: http_agent(),
+        }
+    }
+
+    fn request(&self, method: &str, name: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, &format!("{}{}", self.base_url, name));
+        match &self.authorization {
+            Some(auth) => request.set("Authorization", auth),
+            None => request,
+        }
+    }
+
+    // 部分服务器 PUT 之后不返回 ETag，需要再查询一次
+    fn etag_of(&self, name: &str) -> Result<String, String> {
+        let response = self.request("HEAD", name).call().map_err(http_error)?;
+        response
+            .header("ETag")
+            .map(|s| s.to_string())
+            .ok_or_else(|| format!("WebDAV server returned no ETag for {}", name))
+    }
+}
+
+impl SyncBackend for WebDavBackend {
+    fn list(&self) -> Result<Vec<RemoteObject>, String> {
+        let response = self
+            .request("PROPFIND", "")
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml")
+            .send_string(PROPFIND_BODY)
+            .map_err(http_error)?;
+        let body = String::from_utf8_lossy(&read_body(response)?).to_string();
+        parse_multistatus(&body)
+    }
+
+    fn download(&self, name: &str) -> Result<(Vec<u8>, String), String> {
+        let response = self.request("GET", name).call().map_err(http_error)?;
+        let etag = response.header("ETag").map(|s| s.to_string());
+        let data = read_body(response)?;
+        let etag = match etag {
+            Some(etag) => etag,
+            None => self.etag_of(name)?,
+        };
+        Ok((data, etag))
+    }
+
+    fn upload(
+        &self,
+        name: &str,
+        data: &[u8],
+        expected_etag: Option<&str>,
+    ) -> Result<UploadResult, String> {
+        let request = match expected_etag {
+            Some(etag) => self.request("PUT", name).set("If-Match", etag),
+            None => self.request("PUT", name).set("If-None-Match", "*"),
+        };
+        match request.send_bytes(data) {
+            Ok(response) => {
+                let etag = match response.header("ETag") {
+                    Some(etag) => etag.to_string(),
+                    None => self.etag_of(name)?,
+                };
+                Ok(UploadResult::Stored(etag))
+            }
+            Err(ureq::Error::Status(412, _)) => Ok(UploadResult::Conflict),
+            Err(e) => Err(http_error(e)),
+        }
+    }
+
+    fn delete(&self, name: &str, expected_etag: &str) -> Result<(), String> {
+        match self
+            .request("DELETE", name)
+            .set("If-Match", expected_etag)
+            .call()
+        {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(ureq::Error::Status(412, _)) => Err(format!(
+                "Conflict: {} was changed on the WebDAV server",
+                name
+            )),
+            Err(e) => Err(http_error(e)),
+        }
+    }
+}
+
+// 解析 PROPFIND 返回的 multistatus，忽略命名空间前缀
+fn parse_multistatus(xml: &str) -> Result<Vec<RemoteObject>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut objects = vec![];
+    let mut current = String::new();
+    let mut href = String::new();
+    let mut etag = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                current = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if current == "response" {
+                    href.clear();
+                    etag.clear();
+                }
+            }
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|e| format!("Invalid WebDAV response: {}", e))?;
+                match current.as_str() {
+                    "href" => href.push_str(text.trim()),
+                    "getetag" => etag.push_str(text.trim()),
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.local_name().as_ref() == b"response" {
+                    let name = href
+                        .trim_end_matches('/')
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or("")
+                        .to_string();
+                    if !name.is_empty() && !href.ends_with('/') && !etag.is_empty() {
+                        objects.push(RemoteObject {
+                            name,
+                            etag: etag.clone(),
+                        });
+                    }
+                }
+                current.clear();
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(format!("Invalid WebDAV response: {}", e)),
+        }
+    }
+    Ok(objects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Apache mod_dav 对 Depth: 1 PROPFIND 的响应，集合本身和子目录不算文件
+    #[test]
+    fn parses_propfind_response() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:" xmlns:ns0="DAV:">
+<D:response xmlns:lp1="DAV:">
+<D:href>/dav/safesecrets/</D:href>
+<D:propstat>
+<D:prop>
+<lp1:getetag>"1000-62b3a1e4c5d80"</lp1:getetag>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+</D:response>
+<D:response xmlns:lp1="DAV:">
+<D:href>/dav/safesecrets/000.gpg</D:href>
+<D:propstat>
+<D:prop>
+<lp1:getetag>"2a1-62b3a1e4c5d80"</lp1:getetag>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+</D:response>
+<D:response xmlns:lp1="DAV:">
+<D:href>/dav/safesecrets/archive/</D:href>
+<D:propstat>
+<D:prop>
+<lp1:getetag>"1000-62b3a1e4c5d81"</lp1:getetag>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+</D:response>
+<D:response xmlns:lp1="DAV:">
+<D:href>https://cloud.example.com/dav/safesecrets/001.gpg</D:href>
+<D:propstat>
+<D:prop>
+<lp1:getetag>&quot;5f1-62b3a1e4c5d82&quot;</lp1:getetag>
+</D:prop>
+<D:status>HTTP/1.1 200 OK</D:status>
+</D:propstat>
+</D:response>
+<D:response>
+<D:href>/dav/safesecrets/notes.txt</D:href>
+<D:propstat>
+<D:prop><D:getetag/></D:prop>
+<D:status>HTTP/1.1 404 Not Found</D:status>
+</D:propstat>
+</D:response>
+</D:multistatus>"#;
+        let objects: Vec<(String, String)> = parse_multistatus(xml)
+            .unwrap()
+            .into_iter()
+            .map(|o| (o.name, o.etag))
+            .collect();
+        assert_eq!(
+            objects,
+            vec![
+                ("000.gpg".to_string(), "\"2a1-62b3a1e4c5d80\"".to_string()),
+                ("001.gpg".to_string(), "\"5f1-62b3a1e4c5d82\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_response() {
+        assert!(parse_multistatus("<D:multistatus><D:response></D:multistatus>").is_err());
+    }
+}
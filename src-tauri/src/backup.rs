@@ -0,0 +1,498 @@
+use crate::migration::VERSION_FILE;
+use crate::{get_git_cmd, now_secs, run_git, run_gpg_with_input, verify_security_question};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use tauri::command;
+use zip::write::SimpleFileOptions;
+
+const BACKUP_FORMAT: &str = "safesecrets-backup";
+const BACKUP_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+// 本机设置和同步状态，不含历史时也一起备份
+const CONFIG_DIR: &str = ".git/safesecrets";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Manifest {
+    format: String,
+    version: u32,
+    created_at: u64,
+    history: bool,
+    #[serde(default)]
+    remotes: Vec<Remote>,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Remote {
+    name: String,
+    url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+// 归档中的文件，路径相对于仓库根目录
+type ArchiveFiles = Vec<(String, Vec<u8>)>;
+
+#[derive(Serialize, Debug)]
+pub struct BackupSummary {
+    path: String,
+    version: u32,
+    created_at: u64,
+    files: usize,
+    history: bool,
+}
+
+// 把整个仓库打包成一个 zip，附带每个文件的 SHA-256 清单，
+// 再用 gpg 加密给指定的收件人或口令，gpg 的完整性校验保证归档未被篡改
+#[command]
+pub async fn export_backup(
+    path: String,
+    recipient: Option<String>,
+    passphrase: Option<String>,
+    include_history: bool,
+    answer: String,
+) -> Result<BackupSummary, String> {
+    if !verify_security_question(answer).await? {
+        return Err("Invalid answer".to_string());
+    }
+    let args = match (recipient.as_deref(), passphrase.as_deref()) {
+        (Some(recipient), None) => vec!["--encrypt", "--recipient", recipient],
+        (None, Some(passphrase)) => vec![
+            "--symmetric",
+            "--cipher-algo",
+            "AES256",
+            "--batch",
+            "--passphrase",
+            passphrase,
+        ],
+        _ => return Err("Either recipient or passphrase is required".to_string()),
+    };
+
+    let mut files = vault_files(Path::new("."), include_history)?;
+    files.sort();
+    let created_at = now_secs();
+    let manifest = Manifest {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_VERSION,
+        created_at,
+        history: include_history,
+        remotes: list_remotes()?,
+        files: vec![],
+    };
+    let archive = write_archive(Path::new("."), &files, manifest)?;
+
+    let mut args = args;
+    args.extend(["--yes", "--output", &path]);
+    let output = run_gpg_with_input(&args, &archive)?;
+    if !output.status.success() {
+        return Err(format!(
+            "Error result for gpg command: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(BackupSummary {
+        path,
+        version: BACKUP_VERSION,
+        created_at,
+        files: files.len(),
+        history: include_history,
+    })
+}
+
+// 先在内存中解密并校验清单、版本和每个文件的摘要，全部通过后才写入目标目录；
+// 目标目录必须不存在或为空，不含历史的备份会重新初始化 git 仓库
+#[command]
+pub async fn restore_backup(
+    path: String,
+    target: String,
+    passphrase: Option<String>,
+) -> Result<BackupSummary, String> {
+    let target_dir = PathBuf::from(&target);
+    if target_dir.exists() {
+        let mut entries =
+            fs::read_dir(&target_dir).map_err(|e| format!("Failed to read {}: {}", target, e))?;
+        if entries.next().is_some() {
+            return Err(format!("Target directory {} is not empty", target));
+        }
+    }
+
+    let data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut args = vec!["--quiet", "--batch", "--decrypt"];
+    if let Some(passphrase) = passphrase.as_deref() {
+        args.extend(["--passphrase", passphrase]);
+    }
+    let output = run_gpg_with_input(&args, &data)?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to decrypt backup: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let (manifest, files) = read_archive(&output.stdout)?;
+
+    // 目标目录原本为空，写入失败时整个删除，不留下半个仓库
+    if let Err(e) = unpack(&target_dir, &manifest, &files) {
+        let _ = fs::remove_dir_all(&target_dir);
+        return Err(e);
+    }
+
+    Ok(BackupSummary {
+        path: target,
+        version: manifest.version,
+        created_at: manifest.created_at,
+        files: files.len(),
+        history: manifest.history,
+    })
+}
+
+// 按 files 的顺序写入 zip，清单中的文件列表在这里填充并放在最后
+fn write_archive(root: &Path, files: &[String], mut manifest: Manifest) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(vec![]);
+    let mut writer = zip::ZipWriter::new(&mut buffer);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for file in files {
+        let data =
+            fs::read(root.join(file)).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+        manifest.files.push(ManifestFile {
+            path: file.clone(),
+            size: data.len() as u64,
+            sha256: hex::encode(Sha256::digest(&data)),
+        });
+        writer
+            .start_file(file.as_str(), options)
+            .map_err(|e| format!("Failed to write backup: {}", e))?;
+        writer
+            .write_all(&data)
+            .map_err(|e| format!("Failed to write backup: {}", e))?;
+    }
+    let manifest_data = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    writer
+        .start_file(MANIFEST_FILE, options)
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    writer
+        .write_all(&manifest_data)
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to write backup: {}", e))?;
+    Ok(buffer.into_inner())
+}
+
+fn unpack(dir: &Path, manifest: &Manifest, files: &ArchiveFiles) -> Result<(), String> {
+    for (name, content) in files {
+        let file = dir.join(name);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(&file, content).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+    if !manifest.history {
+        init_repository(dir, manifest)?;
+    }
+    Ok(())
+}
+
+// 返回相对于仓库根目录的路径：所有 .gpg 文件、VERSION 和本机配置，含历史时加上整个 .git 目录
+fn vault_files(root: &Path, include_history: bool) -> Result<Vec<String>, String> {
+    let mut files = vec![];
+    let entries = fs::read_dir(root).map_err(|e| format!("Failed to read vault: {}", e))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_file() && (name.ends_with(".gpg") || name == VERSION_FILE) {
+            files.push(name);
+        }
+    }
+    let extra = if include_history { ".git" } else { CONFIG_DIR };
+    if root.join(extra).is_dir() {
+        collect_dir(root, &root.join(extra), &mut files)?;
+    }
+    Ok(files)
+}
+
+fn collect_dir(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_dir(root, &path, files)?;
+        } else if path.is_file() {
+            let relative = path.strip_prefix(root).map_err(|e| e.to_string())?;
+            let parts: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+fn read_archive(data: &[u8]) -> Result<(Manifest, ArchiveFiles), String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| format!("Invalid backup archive: {}", e))?;
+    let mut manifest_data = vec![];
+    archive
+        .by_name(MANIFEST_FILE)
+        .map_err(|e| format!("Invalid backup archive: {}", e))?
+        .read_to_end(&mut manifest_data)
+        .map_err(|e| format!("Invalid backup archive: {}", e))?;
+    let manifest: Manifest = serde_json::from_slice(&manifest_data)
+        .map_err(|e| format!("Invalid backup manifest: {}", e))?;
+    if manifest.format != BACKUP_FORMAT {
+        return Err("Not a safesecrets backup".to_string());
+    }
+    if manifest.version > BACKUP_VERSION {
+        return Err(format!(
+            "Backup version {} is newer than supported version {}",
+            manifest.version, BACKUP_VERSION
+        ));
+    }
+
+    let mut files = vec![];
+    let mut seen = HashSet::new();
+    for expected in &manifest.files {
+        if !is_safe_path(&expected.path) || !seen.insert(expected.path.as_str()) {
+            return Err(format!("Invalid path in backup: {}", expected.path));
+        }
+        let mut content = vec![];
+        archive
+            .by_name(&expected.path)
+            .map_err(|_| format!("Backup is missing {}", expected.path))?
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Failed to read {} from backup: {}", expected.path, e))?;
+        if content.len() as u64 != expected.size
+            || hex::encode(Sha256::digest(&content)) != expected.sha256
+        {
+            return Err(format!("Backup file {} is corrupted", expected.path));
+        }
+        files.push((expected.path.clone(), content));
+    }
+    // 清单之外的文件说明归档被改动过
+    if archive.len() != manifest.files.len() + 1 {
+        return Err("Backup contains files not listed in the manifest".to_string());
+    }
+    Ok((manifest, files))
+}
+
+fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn list_remotes() -> Result<Vec<Remote>, String> {
+    let mut remotes = vec![];
+    for name in run_git(&["remote"])?.lines() {
+        let url = run_git(&["remote", "get-url", name])?;
+        remotes.push(Remote {
+            name: name.to_string(),
+            url: url.trim().to_string(),
+        });
+    }
+    Ok(remotes)
+}
+
+fn init_repository(dir: &Path, manifest: &Manifest) -> Result<(), String> {
+    let git = |args: &[&str]| -> Result<(), String> {
+        let output = Command::new(get_git_cmd()?)
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to execute git command: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Error result for git command: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(())
+    };
+    git(&["init"])?;
+    git(&["checkout", "-b", "main"])?;
+    for remote in &manifest.remotes {
+        git(&["remote", "add", &remote.name, &remote.url])?;
+    }
+    git(&["add", "./"])?;
+    git(&[
+        "commit",
+        "-m",
+        &format!("restore: {} files from backup", manifest.files.len()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("safesecrets-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn manifest() -> Manifest {
+        Manifest {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            created_at: 1,
+            history: false,
+            remotes: vec![],
+            files: vec![],
+        }
+    }
+
+    // 直接写入任意的清单和文件，模拟被改动的归档
+    fn raw_archive(manifest: Value, files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut buffer = Cursor::new(vec![]);
+        let mut writer = zip::ZipWriter::new(&mut buffer);
+        for (name, data) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer
+            .start_file(MANIFEST_FILE, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(manifest.to_string().as_bytes()).unwrap();
+        writer.finish().unwrap();
+        buffer.into_inner()
+    }
+
+    fn entry(path: &str, data: &[u8]) -> Value {
+        json!({
+            "path": path,
+            "size": data.len(),
+            "sha256": hex::encode(Sha256::digest(data)),
+        })
+    }
+
+    fn manifest_json(files: Vec<Value>) -> Value {
+        json!({
+            "format": BACKUP_FORMAT,
+            "version": BACKUP_VERSION,
+            "created_at": 1,
+            "history": false,
+            "files": files,
+        })
+    }
+
+    #[test]
+    fn round_trips_vault_files() {
+        let dir = test_dir("backup");
+        fs::write(dir.join("000.gpg"), b"index").unwrap();
+        fs::write(dir.join("001.gpg"), b"entry").unwrap();
+        fs::write(dir.join(VERSION_FILE), b"1\n").unwrap();
+        fs::write(dir.join("notes.txt"), b"not a vault file").unwrap();
+        fs::create_dir_all(dir.join(CONFIG_DIR)).unwrap();
+        fs::write(dir.join(CONFIG_DIR).join("settings.json"), b"{}").unwrap();
+
+        let mut files = vault_files(&dir, false).unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                ".git/safesecrets/settings.json",
+                "000.gpg",
+                "001.gpg",
+                VERSION_FILE
+            ]
+        );
+
+        let archive = write_archive(&dir, &files, manifest()).unwrap();
+        let (manifest, read) = read_archive(&archive).unwrap();
+        assert_eq!(manifest.files.len(), 4);
+        for (name, content) in read {
+            assert_eq!(content, fs::read(dir.join(&name)).unwrap());
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_tampered_file() {
+        let archive = raw_archive(
+            manifest_json(vec![entry("001.gpg", b"entry")]),
+            &[("001.gpg", b"entrY")],
+        );
+        assert_eq!(
+            read_archive(&archive).unwrap_err(),
+            "Backup file 001.gpg is corrupted"
+        );
+    }
+
+    #[test]
+    fn rejects_unlisted_file() {
+        let archive = raw_archive(
+            manifest_json(vec![entry("001.gpg", b"entry")]),
+            &[("001.gpg", b"entry"), ("002.gpg", b"extra")],
+        );
+        assert_eq!(
+            read_archive(&archive).unwrap_err(),
+            "Backup contains files not listed in the manifest"
+        );
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        for path in [
+            "../evil.gpg",
+            "/tmp/evil.gpg",
+            "a/../../evil.gpg",
+            "./001.gpg",
+            "",
+        ] {
+            assert!(!is_safe_path(path), "{}", path);
+            let archive = raw_archive(manifest_json(vec![entry(path, b"x")]), &[]);
+            assert_eq!(
+                read_archive(&archive).unwrap_err(),
+                format!("Invalid path in backup: {}", path)
+            );
+        }
+        assert!(is_safe_path(".git/safesecrets/settings.json"));
+
+        let archive = raw_archive(
+            manifest_json(vec![entry("001.gpg", b"x"), entry("001.gpg", b"x")]),
+            &[("001.gpg", b"x")],
+        );
+        assert_eq!(
+            read_archive(&archive).unwrap_err(),
+            "Invalid path in backup: 001.gpg"
+        );
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let mut manifest = manifest_json(vec![]);
+        manifest["version"] = json!(BACKUP_VERSION + 1);
+        let archive = raw_archive(manifest, &[]);
+        assert!(read_archive(&archive)
+            .unwrap_err()
+            .starts_with("Backup version 2 is newer"));
+
+        let mut manifest = manifest_json(vec![]);
+        manifest["format"] = json!("other");
+        let archive = raw_archive(manifest, &[]);
+        assert_eq!(
+            read_archive(&archive).unwrap_err(),
+            "Not a safesecrets backup"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::generator::{password_from_policy, PasswordPolicy};
+use crate::index::IndexMeta;
+use crate::mnemonic::MnemonicSecret;
+use crate::otp::{to_uri, Otp};
+use crate::strength::assess;
+use crate::sync::lock_repo;
+use crate::{
+    add_entry, commit_and_push, decrypt_with_answer, find_entry, get_gpg_email, update_entry,
+};
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+pub const LOGIN_FORMAT: &str = ".login";
+
+// 带类型的条目以 JSON 形式整体加密在 NNN.gpg 中
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TypedSecret {
+    Login(Login),
+    Totp(Otp),
+    Mnemonic(MnemonicSecret),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Login {
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub urls: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub fields: Vec<CustomField>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FieldView {
+    name: String,
+    value: Option<String>,
+    hidden: bool,
+}
+
+// 返回给前端的登录信息，密码和隐藏字段需要单独获取
+#[derive(Serialize, Debug)]
+pub struct LoginFields {
+    username: String,
+    urls: Vec<String>,
+    notes: String,
+    has_password: bool,
+    fields: Vec<FieldView>,
+}
+
+#[command]
+pub async fn add_login(
+    app: String,
+    desc: String,
+    mut login: Login,
+    generate: Option<PasswordPolicy>,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<(), String> {
+    // 传入生成策略时由后端生成密码
+    if let Some(policy) = generate {
+        login.password = password_from_policy(&policy)?;
+    }
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let payload = encode_secret(&TypedSecret::Login(login.clone()))?;
+    let file_number = add_entry(
+        &email,
+        &app,
+        &desc,
+        LOGIN_FORMAT,
+        payload.as_bytes(),
+        login_meta(&app, &login),
+        &answer,
+    )?;
+    commit_and_push(&format!("add: {}.gpg", file_number), &push_to_cloud)
+}
+
+#[command]
+pub async fn update_login(
+    id: String,
+    mut login: Login,
+    generate: Option<PasswordPolicy>,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<(), String> {
+    let item = find_entry(&id)?;
+    if item.meta.kind != "login" {
+        return Err(format!("Secrets {} is not a login", id));
+    }
+
+    if let Some(policy) = generate {
+        login.password = password_from_policy(&policy)?;
+    }
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let payload = encode_secret(&TypedSecret::Login(login.clone()))?;
+    let meta = login_meta(&item.app, &login);
+    update_entry(&email, &id, payload.as_bytes(), meta, &answer)?;
+    commit_and_push(&format!("update: {}.gpg", id), &push_to_cloud)
+}
+
+#[command]
+pub async fn get_login_fields(id: String, answer: String) -> Result<LoginFields, String> {
+    let TypedSecret::Login(login) = decrypt_typed(&id, &answer)? else {
+        return Err(format!("Secrets {} is not a login", id));
+    };
+    Ok(LoginFields {
+        username: login.username,
+        urls: login.urls,
+        notes: login.notes,
+        has_password: !login.password.is_empty(),
+        fields: login
+            .fields
+            .into_iter()
+            .map(|f| FieldView {
+                value: if f.hidden { None } else { Some(f.value) },
+                name: f.name,
+                hidden: f.hidden,
+            })
+            .collect(),
+    })
+}
+
+#[command]
+pub async fn get_secret_field(id: String, field: String, answer: String) -> Result<String, String> {
+    let value = match decrypt_typed(&id, &answer)? {
+        TypedSecret::Login(login) => match field.as_str() {
+            "username" => Some(login.username),
+            "password" => Some(login.password),
+            "notes" => Some(login.notes),
+            "urls" => Some(login.urls.join("\n")),
+            _ => login
+                .fields
+                .into_iter()
+                .find(|f| f.name == field)
+                .map(|f| f.value),
+        },
+        TypedSecret::Totp(otp) => match field.as_str() {
+            "uri" => Some(to_uri(&otp)),
+            "secret" => Some(otp.secret),
+            "issuer" => Some(otp.issuer),
+            "account" => Some(otp.account),
+            _ => None,
+        },
+        TypedSecret::Mnemonic(secret) => match field.as_str() {
+            "mnemonic" => Some(secret.mnemonic),
+            "passphrase" => Some(secret.passphrase),
+            "language" => Some(secret.language),
+            _ => None,
+        },
+    };
+    value.ok_or_else(|| format!("Field {} not found in secrets {}", field, id))
+}
+
+pub fn decrypt_typed(id: &str, answer: &str) -> Result<TypedSecret, String> {
+    let data = decrypt_with_answer(id, answer)?;
+    serde_json::from_slice(&data).map_err(|_| format!("Secrets {} is not a typed entry", id))
+}
+
+pub fn encode_secret(secret: &TypedSecret) -> Result<String, String> {
+    serde_json::to_string(secret).map_err(|e| e.to_string())
+}
+
+// 强度和泄露检查结果缓存在加密索引中，列表无需解密即可标记
+pub fn login_meta(app: &str, login: &Login) -> IndexMeta {
+    let (strength, breached) = assess(&login.password, &[&login.username, app]);
+    IndexMeta {
+        kind: "login".to_string(),
+        urls: login.urls.clone(),
+        strength,
+        breached,
+        ..Default::default()
+    }
+}
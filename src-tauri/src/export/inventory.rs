@@ -0,0 +1,193 @@
+use crate::index::ListItem;
+use crate::strength::WEAK_SCORE;
+use crate::{filter_items, verify_security_question};
+use serde::Serialize;
+use std::fs;
+use tauri::command;
+
+const CSV_HEADER: [&str; 15] = [
+    "id",
+    "app",
+    "desc",
+    "format",
+    "type",
+    "folder",
+    "tags",
+    "urls",
+    "created_at",
+    "updated_at",
+    "rotate_after_days",
+    "expires_at",
+    "strength",
+    "weak",
+    "breached",
+];
+
+#[derive(Serialize, Debug)]
+pub struct InventorySummary {
+    path: String,
+    format: String,
+    entries: usize,
+}
+
+// 只导出索引中的元数据，不读取任何 NNN.gpg，时间为 Unix 秒
+#[derive(Serialize, Debug)]
+struct InventoryRow {
+    id: String,
+    app: String,
+    desc: String,
+    format: String,
+    #[serde(rename = "type")]
+    kind: String,
+    folder: Option<String>,
+    tags: Vec<String>,
+    urls: Vec<String>,
+    created_at: Option<u64>,
+    updated_at: Option<u64>,
+    rotate_after_days: Option<u32>,
+    expires_at: Option<u64>,
+    strength: Option<u8>,
+    weak: Option<bool>,
+    breached: Option<bool>,
+}
+
+impl InventoryRow {
+    fn from_item(item: ListItem) -> InventoryRow {
+        InventoryRow {
+            weak: item.meta.strength.map(|score| score < WEAK_SCORE),
+            id: item.id,
+            app: item.app,
+            desc: item.desc,
+            format: item.format,
+            kind: item.meta.kind,
+            folder: item.meta.folder,
+            tags: item.meta.tags,
+            urls: item.meta.urls,
+            created_at: item.meta.created_at,
+            updated_at: item.meta.updated_at,
+            rotate_after_days: item.meta.rotate_after_days,
+            expires_at: item.meta.expires_at,
+            strength: item.meta.strength,
+            breached: item.meta.breached,
+        }
+    }
+
+    fn csv_record(&self) -> Vec<String> {
+        let opt = |value: Option<String>| value.unwrap_or_default();
+        vec![
+            self.id.clone(),
+            escape_formula(&self.app),
+            escape_formula(&self.desc),
+            self.format.clone(),
+            self.kind.clone(),
+            escape_formula(&opt(self.folder.clone())),
+            // 标签不允许包含逗号，可以直接拼接
+            escape_formula(&self.tags.join(",")),
+            escape_formula(&self.urls.join(" ")),
+            opt(self.created_at.map(|v| v.to_string())),
+            opt(self.updated_at.map(|v| v.to_string())),
+            opt(self.rotate_after_days.map(|v| v.to_string())),
+            opt(self.expires_at.map(|v| v.to_string())),
+            opt(self.strength.map(|v| v.to_string())),
+            opt(self.weak.map(|v| v.to_string())),
+            opt(self.breached.map(|v| v.to_string())),
+        ]
+    }
+}
+
+// 给审计用的凭据清单，筛选条件与 get_secrets_list 相同，顺序按编号从小到大
+#[command]
+pub async fn export_inventory(
+    path: String,
+    format: String,
+    search_str: String,
+    tag: Option<String>,
+    folder: Option<String>,
+    answer: String,
+) -> Result<InventorySummary, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+    let rows: Vec<InventoryRow> = filter_items(&search_str, tag.as_deref(), folder.as_deref())?
+        .into_iter()
+        .map(InventoryRow::from_item)
+        .collect();
+
+    let content = match format.as_str() {
+        "csv" => to_csv(&rows)?,
+        "json" => serde_json::to_vec_pretty(&rows)
+            .map_err(|e| format!("Failed to serialize inventory: {}", e))?,
+        _ => return Err(format!("Unsupported inventory format: {}", format)),
+    };
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(InventorySummary {
+        path,
+        format,
+        entries: rows.len(),
+    })
+}
+
+// 以 = + - @ 开头的单元格会被表格软件当作公式执行，加 ' 前缀按文本显示；JSON 保持原值
+fn escape_formula(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(rows: &[InventoryRow]) -> Result<Vec<u8>, String> {
+    let mut writer = ::csv::Writer::from_writer(vec![]);
+    let write_error = |e: ::csv::Error| format!("Failed to write inventory: {}", e);
+    writer.write_record(CSV_HEADER).map_err(write_error)?;
+    for row in rows {
+        writer.write_record(row.csv_record()).map_err(write_error)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| format!("Failed to write inventory: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(app: &str, desc: &str, folder: Option<&str>, tags: &[&str]) -> InventoryRow {
+        InventoryRow {
+            id: "001".to_string(),
+            app: app.to_string(),
+            desc: desc.to_string(),
+            format: ".txt".to_string(),
+            kind: "login".to_string(),
+            folder: folder.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            urls: vec![],
+            created_at: Some(1),
+            updated_at: None,
+            rotate_after_days: None,
+            expires_at: None,
+            strength: Some(4),
+            weak: Some(false),
+            breached: None,
+        }
+    }
+
+    #[test]
+    fn escapes_formula_cells() {
+        let record = row("=HYPERLINK(\"x\")", "+1", Some("@work"), &["-x", "ok"]).csv_record();
+        assert_eq!(record[1], "'=HYPERLINK(\"x\")");
+        assert_eq!(record[2], "'+1");
+        assert_eq!(record[5], "'@work");
+        assert_eq!(record[6], "'-x,ok");
+        assert_eq!(record[8], "1");
+    }
+
+    #[test]
+    fn keeps_plain_cells() {
+        let record = row("github", "a-b", None, &["dev"]).csv_record();
+        assert_eq!(record[1], "github");
+        assert_eq!(record[2], "a-b");
+        assert_eq!(record[5], "");
+        assert_eq!(record[6], "dev");
+    }
+}
@@ -0,0 +1,17 @@
+pub mod inventory;
+pub mod pass;
+
+use std::fs;
+use std::path::Path;
+
+// 导出目标必须是新目录或空目录，避免覆盖已有文件
+pub fn ensure_empty_dir(dir: &Path) -> Result<(), String> {
+    if dir.exists() {
+        let mut entries =
+            fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        if entries.next().is_some() {
+            return Err(format!("Target directory {} is not empty", dir.display()));
+        }
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))
+}
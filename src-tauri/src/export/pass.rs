@@ -0,0 +1,240 @@
+use super::ensure_empty_dir;
+use crate::attachment::FILE_FORMAT;
+use crate::entry::{decrypt_typed, Login, TypedSecret, LOGIN_FORMAT};
+use crate::index::ListItem;
+use crate::otp::{to_uri, HOTP_FORMAT, OTP_FORMAT};
+use crate::{
+    decrypt_with_answer, get_gpg_email, get_index_lines, run_gpg_with_input,
+    verify_security_question,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::command;
+
+#[derive(Serialize, Debug)]
+pub struct PassExportSummary {
+    path: String,
+    recipients: Vec<String>,
+    exported: usize,
+}
+
+// 按 folder/app/desc.gpg 写出 pass 兼容的目录，只用 gpg 公钥加密，
+// 登录条目转换为 pass 的惯用格式：第一行是密码，后面是 key: value
+#[command]
+pub async fn export_pass(
+    path: String,
+    recipients: Option<Vec<String>>,
+    answer: String,
+) -> Result<PassExportSummary, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+    let recipients = match recipients {
+        Some(recipients) if !recipients.is_empty() => recipients,
+        _ => vec![get_gpg_email().await?],
+    };
+
+    let store = PathBuf::from(&path);
+    ensure_empty_dir(&store)?;
+    let result = write_store(&store, &recipients, &answer);
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&store);
+    }
+    Ok(PassExportSummary {
+        path,
+        recipients,
+        exported: result?,
+    })
+}
+
+fn write_store(store: &Path, recipients: &[String], answer: &str) -> Result<usize, String> {
+    fs::write(
+        store.join(".gpg-id"),
+        format!("{}\n", recipients.join("\n")),
+    )
+    .map_err(|e| format!("Failed to write .gpg-id: {}", e))?;
+
+    let mut used = HashSet::new();
+    let mut exported = 0;
+    for line in get_index_lines()? {
+        let item = ListItem::parse(&line);
+        let mut name = entry_name(&item);
+        if !used.insert(name.clone()) {
+            name = format!("{}-{}", name, item.id);
+            used.insert(name.clone());
+        }
+
+        let mut content = entry_content(&item, answer)?;
+        let file = store.join(format!("{}.gpg", name));
+        if !inside_store(store, &file) {
+            return Err(format!(
+                "Refusing to write {} outside {}",
+                name,
+                store.display()
+            ));
+        }
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let file_path = file.to_string_lossy().to_string();
+        let mut args = vec!["--encrypt", "--batch", "--yes", "--output", &file_path];
+        for recipient in recipients {
+            args.extend(["--recipient", recipient.as_str()]);
+        }
+        let output = run_gpg_with_input(&args, &content);
+        content.fill(0);
+        let output = output?;
+        if !output.status.success() {
+            return Err(format!(
+                "Error result for gpg command: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        exported += 1;
+    }
+    Ok(exported)
+}
+
+// 登录和纯文本只用 desc 作为文件名，其余格式保留扩展名，
+// TOTP 和 HOTP 用 desc-otp 和登录条目并列，附件使用原文件名
+fn entry_name(item: &ListItem) -> String {
+    let file_name = match item.format.as_str() {
+        LOGIN_FORMAT | ".txt" => clean(&item.desc),
+        OTP_FORMAT | HOTP_FORMAT => format!("{}-otp", clean(&item.desc)),
+        FILE_FORMAT => item
+            .meta
+            .file_name
+            .as_deref()
+            .map(clean)
+            .filter(|name| !name.is_empty() && !name.starts_with('.'))
+            .unwrap_or_else(|| clean(&item.desc)),
+        format => format!("{}{}", clean(&item.desc), format),
+    };
+    // 旧版本或其他保管库导入的文件夹可能含有 . 和 ..，这些路径段直接跳过
+    let mut parts: Vec<String> = vec![];
+    if let Some(folder) = &item.meta.folder {
+        parts.extend(
+            folder
+                .split('/')
+                .filter(|part| !matches!(part.trim(), "" | "." | ".."))
+                .map(clean),
+        );
+    }
+    parts.push(clean(&item.app));
+    parts.push(file_name);
+    parts.join("/")
+}
+
+// 名字中的路径段已经过滤，写入前再确认文件仍在导出目录之内
+fn inside_store(store: &Path, file: &Path) -> bool {
+    file.strip_prefix(store).is_ok_and(|rest| {
+        rest.components()
+            .all(|part| matches!(part, Component::Normal(_)))
+    })
+}
+
+// 路径分隔符替换为 -，整段为 . 或 .. 时同样替换，避免写到导出目录之外
+fn clean(value: &str) -> String {
+    let segment = value.replace(['/', '\\'], "-");
+    match segment.as_str() {
+        "" | "." | ".." => "-".repeat(segment.len().max(1)),
+        _ => segment,
+    }
+}
+
+fn entry_content(item: &ListItem, answer: &str) -> Result<Vec<u8>, String> {
+    if !matches!(
+        item.meta.kind.as_str(),
+        "login" | "totp" | "hotp" | "mnemonic"
+    ) {
+        return decrypt_with_answer(&item.id, answer);
+    }
+    let text = match decrypt_typed(&item.id, answer)? {
+        TypedSecret::Login(login) => login_text(&login),
+        TypedSecret::Totp(otp) => format!("{}\n", to_uri(&otp)),
+        TypedSecret::Mnemonic(secret) => {
+            let mut text = format!("{}\nlanguage: {}\n", secret.mnemonic, secret.language);
+            if !secret.passphrase.is_empty() {
+                text.push_str(&format!("passphrase: {}\n", secret.passphrase));
+            }
+            text
+        }
+    };
+    Ok(text.into_bytes())
+}
+
+fn login_text(login: &Login) -> String {
+    let mut text = format!("{}\n", login.password);
+    if !login.username.is_empty() {
+        text.push_str(&format!("login: {}\n", login.username));
+    }
+    for url in &login.urls {
+        text.push_str(&format!("url: {}\n", url));
+    }
+    for field in &login.fields {
+        text.push_str(&format!(
+            "{}: {}\n",
+            field.name,
+            field.value.replace('\n', " ")
+        ));
+    }
+    if !login.notes.is_empty() {
+        text.push('\n');
+        text.push_str(&login.notes);
+        if !login.notes.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(line: &str, folder: Option<&str>) -> ListItem {
+        let mut item = ListItem::parse(line);
+        item.meta.folder = folder.map(|f| f.to_string());
+        item
+    }
+
+    #[test]
+    fn names_entries() {
+        assert_eq!(
+            entry_name(&item("001.github.token.txt", None)),
+            "github/token"
+        );
+        assert_eq!(
+            entry_name(&item("002.aws.root.login", Some("work/cloud"))),
+            "work/cloud/aws/root"
+        );
+        assert_eq!(entry_name(&item("003.aws.root.totp", None)), "aws/root-otp");
+        assert_eq!(entry_name(&item("004.ssh.key.pem", None)), "ssh/key.pem");
+        assert_eq!(
+            entry_name(&item("005.github%2Ecom.a/b.txt", None)),
+            "github.com/a-b"
+        );
+    }
+
+    #[test]
+    fn keeps_names_inside_store() {
+        assert_eq!(
+            entry_name(&item("001.x.y.txt", Some("../../etc/./cron.d"))),
+            "etc/cron.d/x/y"
+        );
+        assert_eq!(entry_name(&item("002.%2E%2E.%2E%2E.txt", None)), "--/--");
+        assert_eq!(entry_name(&item("003.%2E.y.txt", Some(".."))), "-/y");
+        assert!(inside_store(Path::new("./out"), Path::new("./out/a/b.gpg")));
+        for name in ["../x", "a/../../x", "/etc/passwd"] {
+            let store = Path::new("/tmp/store");
+            assert!(
+                !inside_store(store, &store.join(format!("{}.gpg", name))),
+                "{}",
+                name
+            );
+        }
+    }
+}
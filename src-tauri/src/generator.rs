@@ -0,0 +1,332 @@
+use serde::Deserialize;
+use std::sync::OnceLock;
+use tauri::command;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.?/~";
+const AMBIGUOUS: &str = "0O1lI|`'\"";
+
+static EFF_WORDLIST: &str = include_str!("../resources/eff_large_wordlist.txt");
+static EFF_WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+    pub symbol_set: Option<String>,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            symbol_set: None,
+            min_digits: 1,
+            min_symbols: 1,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PassphrasePolicy {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    pub include_number: bool,
+}
+
+impl Default for PassphrasePolicy {
+    fn default() -> Self {
+        PassphrasePolicy {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            include_number: false,
+        }
+    }
+}
+
+#[command]
+pub async fn generate_password(policy: Option<PasswordPolicy>) -> Result<String, String> {
+    password_from_policy(&policy.unwrap_or_default())
+}
+
+#[command]
+pub async fn generate_passphrase(policy: Option<PassphrasePolicy>) -> Result<String, String> {
+    passphrase_from_policy(&policy.unwrap_or_default())
+}
+
+pub fn password_from_policy(policy: &PasswordPolicy) -> Result<String, String> {
+    // 自定义符号集中重复的字符会提高它的出现概率，只保留一次
+    let filter = |set: &str| -> Vec<char> {
+        let mut chars: Vec<char> = vec![];
+        for c in set.chars() {
+            if !chars.contains(&c) && (!policy.exclude_ambiguous || !AMBIGUOUS.contains(c)) {
+                chars.push(c);
+            }
+        }
+        chars
+    };
+    let symbol_set = policy.symbol_set.as_deref().unwrap_or(SYMBOLS);
+
+    // 每个启用的字符类至少出现一次，数字和符号按最少数量补足
+    let mut classes: Vec<(Vec<char>, usize)> = vec![];
+    if policy.lowercase {
+        classes.push((filter(LOWERCASE), 1));
+    }
+    if policy.uppercase {
+        classes.push((filter(UPPERCASE), 1));
+    }
+    if policy.digits {
+        classes.push((filter(DIGITS), policy.min_digits.max(1)));
+    }
+    if policy.symbols {
+        classes.push((filter(symbol_set), policy.min_symbols.max(1)));
+    }
+    classes.retain(|(set, _)| !set.is_empty());
+    if classes.is_empty() {
+        return Err("At least one character class must be enabled".to_string());
+    }
+
+    let required: usize = classes.iter().map(|(_, min)| min).sum();
+    if policy.length < required || policy.length > 1024 {
+        return Err(format!(
+            "Password length must be between {} and 1024",
+            required
+        ));
+    }
+
+    let all: Vec<char> = classes.iter().flat_map(|(set, _)| set.clone()).collect();
+    let mut password: Vec<char> = vec![];
+    for (set, min) in &classes {
+        for _ in 0..*min {
+            password.push(set[random_below(set.len())?]);
+        }
+    }
+    while password.len() < policy.length {
+        password.push(all[random_below(all.len())?]);
+    }
+
+    // Fisher-Yates 洗牌，打乱必选字符的位置
+    for i in (1..password.len()).rev() {
+        let j = random_below(i + 1)?;
+        password.swap(i, j);
+    }
+    Ok(password.into_iter().collect())
+}
+
+pub fn passphrase_from_policy(policy: &PassphrasePolicy) -> Result<String, String> {
+    if policy.words < 3 || policy.words > 64 {
+        return Err("Passphrase must have between 3 and 64 words".to_string());
+    }
+
+    let list = eff_words();
+    let mut words: Vec<String> = vec![];
+    for _ in 0..policy.words {
+        let word = list[random_below(list.len())?];
+        if policy.capitalize {
+            let mut chars = word.chars();
+            let first = chars.next().map(|c| c.to_uppercase().to_string());
+            words.push(format!("{}{}", first.unwrap_or_default(), chars.as_str()));
+        } else {
+            words.push(word.to_string());
+        }
+    }
+    if policy.include_number {
+        let i = random_below(words.len())?;
+        words[i] = format!("{}{}", words[i], random_below(10)?);
+    }
+    Ok(words.join(&policy.separator))
+}
+
+fn eff_words() -> &'static [&'static str] {
+    EFF_WORDS.get_or_init(|| {
+        EFF_WORDLIST
+            .lines()
+            .filter_map(|line| line.split('\t').nth(1))
+            .collect()
+    })
+}
+
+// 使用系统 CSPRNG，拒绝采样避免取模偏差
+fn random_below(n: usize) -> Result<usize, String> {
+    let n = n as u64;
+    let zone = u64::MAX - (u64::MAX % n);
+    loop {
+        let mut buf = [0u8; 8];
+        getrandom::fill(&mut buf).map_err(|e| format!("Failed to get random bytes: {}", e))?;
+        let value = u64::from_le_bytes(buf);
+        if value < zone {
+            return Ok((value % n) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(password: &str, set: &str) -> usize {
+        password.chars().filter(|c| set.contains(*c)).count()
+    }
+
+    #[test]
+    fn password_meets_policy() {
+        let policy = PasswordPolicy {
+            length: 12,
+            min_digits: 4,
+            min_symbols: 3,
+            ..Default::default()
+        };
+        for _ in 0..200 {
+            let password = password_from_policy(&policy).unwrap();
+            assert_eq!(password.chars().count(), 12);
+            assert!(count(&password, LOWERCASE) >= 1);
+            assert!(count(&password, UPPERCASE) >= 1);
+            assert!(count(&password, DIGITS) >= 4);
+            assert!(count(&password, SYMBOLS) >= 3);
+        }
+    }
+
+    #[test]
+    fn rejects_length_out_of_bounds() {
+        let policy = |length| PasswordPolicy {
+            length,
+            min_digits: 3,
+            min_symbols: 3,
+            ..Default::default()
+        };
+        // 小写 1 + 大写 1 + 数字 3 + 符号 3
+        assert!(password_from_policy(&policy(8)).is_ok());
+        assert_eq!(
+            password_from_policy(&policy(7)).unwrap_err(),
+            "Password length must be between 8 and 1024"
+        );
+        assert!(password_from_policy(&policy(1024)).is_ok());
+        assert!(password_from_policy(&policy(1025)).is_err());
+    }
+
+    #[test]
+    fn excludes_ambiguous_characters() {
+        let policy = PasswordPolicy {
+            length: 200,
+            exclude_ambiguous: true,
+            symbol_set: Some("|`'\"!".to_string()),
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            let password = password_from_policy(&policy).unwrap();
+            assert_eq!(count(&password, AMBIGUOUS), 0);
+            assert!(password.contains('!'));
+        }
+    }
+
+    #[test]
+    fn rejects_empty_classes() {
+        let none = PasswordPolicy {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            ..Default::default()
+        };
+        let error = "At least one character class must be enabled";
+        assert_eq!(password_from_policy(&none).unwrap_err(), error);
+
+        // 只剩一个字符类且它的字符都被当作易混淆字符过滤掉
+        let filtered = PasswordPolicy {
+            symbols: true,
+            symbol_set: Some("|`".to_string()),
+            exclude_ambiguous: true,
+            ..none
+        };
+        assert_eq!(password_from_policy(&filtered).unwrap_err(), error);
+    }
+
+    #[test]
+    fn deduplicates_symbol_set() {
+        let policy = PasswordPolicy {
+            length: 1000,
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbol_set: Some("!!!!!!!!!#".to_string()),
+            ..Default::default()
+        };
+        let password = password_from_policy(&policy).unwrap();
+        // 去重后两个符号各占一半，不去重时 # 只有约 10%
+        assert!(count(&password, "#") > 350);
+    }
+
+    #[test]
+    fn passphrase_meets_policy() {
+        let words = eff_words();
+        let policy = PassphrasePolicy {
+            words: 5,
+            separator: " ".to_string(),
+            ..Default::default()
+        };
+        let passphrase = passphrase_from_policy(&policy).unwrap();
+        let parts: Vec<&str> = passphrase.split(' ').collect();
+        assert_eq!(parts.len(), 5);
+        assert!(parts.iter().all(|part| words.contains(part)));
+
+        let policy = PassphrasePolicy {
+            words: 4,
+            separator: ".".to_string(),
+            include_number: true,
+            ..Default::default()
+        };
+        let passphrase = passphrase_from_policy(&policy).unwrap();
+        let parts: Vec<&str> = passphrase.split('.').collect();
+        assert_eq!(parts.len(), 4);
+        let numbered: Vec<&&str> = parts.iter().filter(|part| !words.contains(part)).collect();
+        assert_eq!(numbered.len(), 1);
+        let (word, digit) = numbered[0].split_at(numbered[0].len() - 1);
+        assert!(words.contains(&word));
+        assert!(digit.chars().all(|c| c.is_ascii_digit()));
+
+        for words in [2, 65] {
+            let policy = PassphrasePolicy {
+                words,
+                ..Default::default()
+            };
+            assert!(passphrase_from_policy(&policy).is_err());
+        }
+    }
+
+    #[test]
+    fn random_below_stays_in_range() {
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            let value = random_below(7).unwrap();
+            assert!(value < 7);
+            seen[value] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+        assert_eq!(random_below(1).unwrap(), 0);
+    }
+
+    #[test]
+    fn loads_eff_wordlist() {
+        let words = eff_words();
+        assert_eq!(words.len(), 7776);
+        assert_eq!(words[0], "abacus");
+        assert_eq!(words[7775], "zoom");
+    }
+}
@@ -0,0 +1,164 @@
+use super::ImportRecord;
+use crate::entry::{CustomField, Login};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Bitwarden 未加密的 JSON 导出：1 登录，2 安全笔记，3 银行卡，4 身份；
+// 其他类型记入 skipped
+pub fn parse(data: &[u8], skipped: &mut Vec<String>) -> Result<Vec<ImportRecord>, String> {
+    let export: Value =
+        serde_json::from_slice(data).map_err(|e| format!("Invalid Bitwarden export: {}", e))?;
+    if export["encrypted"].as_bool() == Some(true) {
+        return Err("Encrypted Bitwarden exports are not supported, export as JSON".to_string());
+    }
+
+    let folders: HashMap<&str, &str> = export["folders"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| Some((f["id"].as_str()?, f["name"].as_str()?)))
+        .collect();
+
+    let mut records = vec![];
+    for item in export["items"].as_array().into_iter().flatten() {
+        let mut record = ImportRecord {
+            title: text(&item["name"]),
+            folder: item["folderId"]
+                .as_str()
+                .and_then(|id| folders.get(id))
+                .map(|name| name.to_string()),
+            ..Default::default()
+        };
+        let fields: Vec<CustomField> = item["fields"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|f| CustomField {
+                name: text(&f["name"]),
+                value: text(&f["value"]),
+                hidden: f["type"].as_u64() == Some(1),
+            })
+            .collect();
+
+        match item["type"].as_u64() {
+            Some(1) => {
+                let login = &item["login"];
+                record.login = Some(Login {
+                    username: text(&login["username"]),
+                    password: text(&login["password"]),
+                    urls: login["uris"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(|u| text(&u["uri"]))
+                        .filter(|u| !u.is_empty())
+                        .collect(),
+                    notes: text(&item["notes"]),
+                    fields,
+                });
+                record.totp = login["totp"].as_str().map(|s| s.to_string());
+            }
+            Some(2) => {
+                record.note = Some((".txt".to_string(), text(&item["notes"])));
+            }
+            // 银行卡和身份信息整体保存为 JSON
+            Some(3) | Some(4) => {
+                let key = if item["type"].as_u64() == Some(3) {
+                    "card"
+                } else {
+                    "identity"
+                };
+                let mut value = item[key].clone();
+                if let Value::Object(map) = &mut value {
+                    map.retain(|_, v| !v.is_null());
+                    if !text(&item["notes"]).is_empty() {
+                        map.insert("notes".to_string(), item["notes"].clone());
+                    }
+                    for field in &fields {
+                        map.insert(field.name.clone(), Value::String(field.value.clone()));
+                    }
+                }
+                let content = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+                record.note = Some((".json".to_string(), content));
+            }
+            kind => {
+                let kind = kind.map_or("unknown".to_string(), |k| k.to_string());
+                skipped.push(format!("{}: unsupported item type {}", record.title, kind));
+                continue;
+            }
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "encrypted": false,
+        "folders": [{ "id": "f1", "name": "Work" }],
+        "items": [
+            {
+                "type": 1, "name": "Mail", "folderId": "f1", "notes": "n",
+                "login": {
+                    "username": "bob", "password": " pw ", "totp": "JBSWY3DP",
+                    "uris": [{ "uri": "https://mail.example.com" }, { "uri": null }]
+                },
+                "fields": [{ "name": "pin", "value": "1234", "type": 1 }]
+            },
+            { "type": 2, "name": "Wifi", "folderId": null, "notes": "ssid: home" },
+            {
+                "type": 3, "name": "Visa", "notes": "card note",
+                "card": { "number": "4111", "code": "123", "brand": null }
+            },
+            { "type": 5, "name": "Deploy key" }
+        ]
+    }"#;
+
+    #[test]
+    fn parses_item_types() {
+        let mut skipped = vec![];
+        let records = parse(EXPORT.as_bytes(), &mut skipped).unwrap();
+        assert_eq!(records.len(), 3);
+
+        let login = records[0].login.as_ref().unwrap();
+        assert_eq!(records[0].folder.as_deref(), Some("Work"));
+        assert_eq!(records[0].totp.as_deref(), Some("JBSWY3DP"));
+        assert_eq!(login.password, " pw ");
+        assert_eq!(login.urls, vec!["https://mail.example.com"]);
+        assert!(login.fields[0].hidden);
+
+        assert_eq!(
+            records[1].note,
+            Some((".txt".to_string(), "ssid: home".to_string()))
+        );
+
+        let (format, content) = records[2].note.as_ref().unwrap();
+        let card: Value = serde_json::from_str(content).unwrap();
+        assert_eq!(format, ".json");
+        assert_eq!(card["number"], "4111");
+        assert_eq!(card["notes"], "card note");
+        assert!(card.get("brand").is_none());
+
+        assert_eq!(skipped, vec!["Deploy key: unsupported item type 5"]);
+    }
+
+    #[test]
+    fn rejects_encrypted_export() {
+        let mut skipped = vec![];
+        let result = parse(br#"{"encrypted": true, "items": []}"#, &mut skipped);
+        assert!(result
+            .unwrap_err()
+            .starts_with("Encrypted Bitwarden exports"));
+    }
+}
@@ -0,0 +1,210 @@
+use super::ImportRecord;
+use crate::entry::Login;
+use serde::Deserialize;
+
+// 列名映射，未指定的字段按常见导出格式的列名自动识别
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CsvMapping {
+    title: Option<String>,
+    url: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    notes: Option<String>,
+    totp: Option<String>,
+    folder: Option<String>,
+    tags: Option<String>,
+}
+
+// 覆盖 Chrome、Firefox、Safari、1Password 和 Bitwarden 的 CSV 导出
+const TITLE_COLUMNS: [&str; 2] = ["title", "name"];
+const URL_COLUMNS: [&str; 5] = ["url", "login_uri", "uri", "website", "origin"];
+const USERNAME_COLUMNS: [&str; 4] = ["username", "login_username", "user", "email"];
+const PASSWORD_COLUMNS: [&str; 2] = ["password", "login_password"];
+const NOTES_COLUMNS: [&str; 4] = ["notes", "note", "extra", "comments"];
+const TOTP_COLUMNS: [&str; 4] = ["otpauth", "totp", "login_totp", "one-time password"];
+const FOLDER_COLUMNS: [&str; 3] = ["folder", "grouping", "group"];
+const TAGS_COLUMNS: [&str; 1] = ["tags"];
+
+// 没有任何可导入内容的行记入 skipped
+pub fn parse(
+    data: &[u8],
+    mapping: &CsvMapping,
+    skipped: &mut Vec<String>,
+) -> Result<Vec<ImportRecord>, String> {
+    let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Invalid CSV: {}", e))?
+        .iter()
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_lowercase())
+        .collect();
+
+    let column = |mapped: &Option<String>, aliases: &[&str]| -> Result<Option<usize>, String> {
+        match mapped {
+            Some(name) => headers
+                .iter()
+                .position(|h| *h == name.trim().to_lowercase())
+                .map(Some)
+                .ok_or_else(|| format!("Column {} not found in CSV", name)),
+            None => Ok(aliases
+                .iter()
+                .find_map(|alias| headers.iter().position(|h| h == alias))),
+        }
+    };
+    let title = column(&mapping.title, &TITLE_COLUMNS)?;
+    let url = column(&mapping.url, &URL_COLUMNS)?;
+    let username = column(&mapping.username, &USERNAME_COLUMNS)?;
+    let password = column(&mapping.password, &PASSWORD_COLUMNS)?;
+    let notes = column(&mapping.notes, &NOTES_COLUMNS)?;
+    let totp = column(&mapping.totp, &TOTP_COLUMNS)?;
+    let folder = column(&mapping.folder, &FOLDER_COLUMNS)?;
+    let tags = column(&mapping.tags, &TAGS_COLUMNS)?;
+    if password.is_none() {
+        return Err("No password column found in CSV, please provide a mapping".to_string());
+    }
+
+    let mut records = vec![];
+    for (line, row) in reader.records().enumerate() {
+        let row = row.map_err(|e| format!("Invalid CSV: {}", e))?;
+        // 密码和备注按原样保留，首尾空格也是内容的一部分
+        let raw = |index: Option<usize>| {
+            index
+                .and_then(|i| row.get(i))
+                .unwrap_or_default()
+                .to_string()
+        };
+        let get = |index: Option<usize>| raw(index).trim().to_string();
+        let url_value = get(url);
+        // Firefox 的导出没有标题列，用网址的主机名代替
+        let title_value = match get(title) {
+            t if t.is_empty() => host_of(&url_value),
+            t => t,
+        };
+        let login = Login {
+            username: get(username),
+            password: raw(password),
+            urls: if url_value.is_empty() {
+                vec![]
+            } else {
+                vec![url_value]
+            },
+            notes: raw(notes),
+            fields: vec![],
+        };
+        // 只有备注的行（如 Bitwarden 的安全笔记）作为笔记导入
+        let (login, note) =
+            if login.password.is_empty() && login.username.is_empty() && login.urls.is_empty() {
+                if login.notes.trim().is_empty() {
+                    // 第一行是表头，数据从第 2 行开始
+                    skipped.push(format!(
+                        "row {}: no password, username, URL or notes",
+                        line + 2
+                    ));
+                    continue;
+                }
+                (None, Some((".txt".to_string(), login.notes)))
+            } else {
+                (Some(login), None)
+            };
+        records.push(ImportRecord {
+            title: title_value,
+            login,
+            note,
+            totp: Some(get(totp)).filter(|t| !t.is_empty()),
+            folder: Some(get(folder)).filter(|f| !f.is_empty()),
+            tags: get(tags)
+                .split([',', ';'])
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            ..Default::default()
+        });
+    }
+    Ok(records)
+}
+
+fn host_of(url: &str) -> String {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.rsplit('@').next().unwrap_or_default();
+    host.split(':').next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_csv(data: &str, mapping: &CsvMapping) -> (Vec<ImportRecord>, Vec<String>) {
+        let mut skipped = vec![];
+        let records = parse(data.as_bytes(), mapping, &mut skipped).unwrap();
+        (records, skipped)
+    }
+
+    #[test]
+    fn keeps_password_and_notes_verbatim() {
+        let data = "name,url,username,password,note\n\
+                    GitHub , https://github.com/login , alice ,  pass word  , line \n";
+        let (records, _) = parse_csv(data, &CsvMapping::default());
+        let login = records[0].login.as_ref().unwrap();
+        assert_eq!(records[0].title, "GitHub");
+        assert_eq!(login.username, "alice");
+        assert_eq!(login.password, "  pass word  ");
+        assert_eq!(login.notes, " line ");
+        assert_eq!(login.urls, vec!["https://github.com/login"]);
+    }
+
+    #[test]
+    fn maps_bitwarden_csv() {
+        let data = "\u{feff}folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+                    Work,,login,Mail,,,0,https://mail.example.com,bob,hunter2,JBSWY3DP\n\
+                    ,,note,Wifi,ssid: home,,0,,,,\n\
+                    ,,note,Empty,,,0,,,,\n";
+        let (records, skipped) = parse_csv(data, &CsvMapping::default());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].folder.as_deref(), Some("Work"));
+        assert_eq!(records[0].totp.as_deref(), Some("JBSWY3DP"));
+        assert_eq!(records[0].login.as_ref().unwrap().password, "hunter2");
+        assert!(records[1].login.is_none());
+        assert_eq!(
+            records[1].note,
+            Some((".txt".to_string(), "ssid: home".to_string()))
+        );
+        assert_eq!(skipped, vec!["row 4: no password, username, URL or notes"]);
+    }
+
+    #[test]
+    fn titles_firefox_rows_by_host() {
+        let data = "url,username,password\nhttps://user@example.com:8443/path,carol,pw\n";
+        let (records, _) = parse_csv(data, &CsvMapping::default());
+        assert_eq!(records[0].title, "example.com");
+    }
+
+    #[test]
+    fn uses_explicit_mapping() {
+        let data = "Site,Login,Secret,Labels\nBank,dave,pw,finance; home\n";
+        let mapping = CsvMapping {
+            title: Some("site".to_string()),
+            username: Some("Login".to_string()),
+            password: Some("Secret".to_string()),
+            tags: Some("Labels".to_string()),
+            ..Default::default()
+        };
+        let (records, _) = parse_csv(data, &mapping);
+        assert_eq!(records[0].title, "Bank");
+        assert_eq!(records[0].login.as_ref().unwrap().username, "dave");
+        assert_eq!(records[0].tags, vec!["finance", "home"]);
+
+        let missing = CsvMapping {
+            password: Some("Pass".to_string()),
+            ..Default::default()
+        };
+        let mut skipped = vec![];
+        assert_eq!(
+            parse(data.as_bytes(), &missing, &mut skipped).unwrap_err(),
+            "Column Pass not found in CSV"
+        );
+        let none = parse(b"a,b\n1,2\n", &CsvMapping::default(), &mut skipped);
+        assert!(none.unwrap_err().starts_with("No password column"));
+    }
+}
@@ -0,0 +1,385 @@
+use super::{commit_import, sanitize_name, write_entries, ImportSummary, PendingEntry};
+use crate::attachment::{check_size, file_meta, guess_mime, FILE_FORMAT};
+use crate::entry::{encode_secret, login_meta, CustomField, Login, TypedSecret, LOGIN_FORMAT};
+use crate::index::{normalize_folder, validate_tag};
+use crate::sync::lock_repo;
+use crate::{get_gpg_email, verify_security_question};
+use base64::Engine;
+use keepass::db::{Entry, Group, Meta, Node, Value};
+use keepass::{Database, DatabaseKey};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fs;
+use tauri::command;
+
+// 这些字段映射到 Login 的固定字段，其余字段作为自定义字段导入
+const STANDARD_FIELDS: [&str; 5] = ["Title", "UserName", "Password", "URL", "Notes"];
+
+#[command]
+pub async fn import_keepass(
+    path: String,
+    password: Option<String>,
+    keyfile: Option<String>,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<ImportSummary, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+
+    let data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let key = database_key(password.as_deref(), keyfile.as_deref())?;
+    let db = Database::parse(&data, key.clone())
+        .map_err(|e| format!("Failed to open KeePass database: {}", e))?;
+
+    // keepass 库不解析条目上的附件引用，从解密后的 XML 中单独读取
+    let xml = Database::get_xml(&mut data.as_slice(), key)
+        .map_err(|e| format!("Failed to open KeePass database: {}", e))?;
+    let binaries = entry_binaries(&xml)?;
+    let attachments = attachment_contents(&db);
+
+    let mut found: Vec<(String, &Entry)> = vec![];
+    collect_entries(&db.root, "", &db.meta, &mut found);
+
+    let mut summary = ImportSummary::default();
+    let mut pending = vec![];
+    for (folder, entry) in found {
+        let title = entry.get_title().unwrap_or_default();
+        let app = sanitize_name(title, "keepass");
+        let desc = sanitize_name(entry.get_username().unwrap_or_default(), "login");
+        let login = entry_login(entry);
+
+        let mut meta = login_meta(&app, &login);
+        meta.folder = normalize_folder(&folder).unwrap_or(None);
+        meta.tags = entry
+            .tags
+            .iter()
+            .filter_map(|tag| validate_tag(tag).ok())
+            .collect();
+        let times = &entry.times;
+        meta.created_at = timestamp(times.get_creation().map(|t| t.and_utc().timestamp()));
+        meta.updated_at = timestamp(
+            times
+                .get_last_modification()
+                .map(|t| t.and_utc().timestamp()),
+        );
+        if times.expires {
+            meta.expires_at = timestamp(times.get_expiry().map(|t| t.and_utc().timestamp()));
+        }
+        pending.push(PendingEntry {
+            app: app.clone(),
+            desc: desc.clone(),
+            format: LOGIN_FORMAT.to_string(),
+            data: encode_secret(&TypedSecret::Login(login))?.into_bytes(),
+            meta: meta.clone(),
+        });
+
+        let uuid = base64_uuid(entry);
+        for (name, reference) in binaries.get(&uuid).into_iter().flatten() {
+            let Some(content) = attachments.get(reference) else {
+                summary
+                    .skipped
+                    .push(format!("{}/{}: missing attachment", title, name));
+                continue;
+            };
+            if let Err(e) = check_size(content.len()) {
+                summary.skipped.push(format!("{}/{}: {}", title, name, e));
+                continue;
+            }
+            let mut file = file_meta(name, guess_mime(name), content.len() as u64);
+            file.folder = meta.folder.clone();
+            file.tags = meta.tags.clone();
+            file.created_at = meta.created_at;
+            file.updated_at = meta.updated_at;
+            pending.push(PendingEntry {
+                app: app.clone(),
+                desc: sanitize_name(name, "attachment"),
+                format: FILE_FORMAT.to_string(),
+                data: content.clone(),
+                meta: file,
+            });
+            summary.attachments += 1;
+        }
+    }
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    write_entries(&email, pending, &answer, &mut summary)?;
+    commit_import("keepass", &summary, &push_to_cloud)?;
+    Ok(summary)
+}
+
+fn database_key(password: Option<&str>, keyfile: Option<&str>) -> Result<DatabaseKey, String> {
+    if password.is_none() && keyfile.is_none() {
+        return Err("Either password or keyfile is required".to_string());
+    }
+    let mut key = DatabaseKey::new();
+    if let Some(password) = password {
+        key = key.with_password(password);
+    }
+    if let Some(path) = keyfile {
+        let mut file =
+            fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        key = key
+            .with_keyfile(&mut file)
+            .map_err(|e| format!("Failed to read keyfile: {}", e))?;
+    }
+    Ok(key)
+}
+
+// 根分组不计入文件夹路径，回收站中的条目不导入
+fn collect_entries<'a>(
+    group: &'a Group,
+    path: &str,
+    meta: &Meta,
+    found: &mut Vec<(String, &'a Entry)>,
+) {
+    for node in &group.children {
+        match node {
+            Node::Group(child) => {
+                if Some(child.uuid) == meta.recyclebin_uuid {
+                    continue;
+                }
+                let child_path = if path.is_empty() {
+                    child.name.replace('/', "-")
+                } else {
+                    format!("{}/{}", path, child.name.replace('/', "-"))
+                };
+                collect_entries(child, &child_path, meta, found);
+            }
+            Node::Entry(entry) => found.push((path.to_string(), entry)),
+        }
+    }
+}
+
+fn entry_login(entry: &Entry) -> Login {
+    let mut fields: Vec<CustomField> = entry
+        .fields
+        .iter()
+        .filter(|(name, _)| !STANDARD_FIELDS.contains(&name.as_str()))
+        .map(|(name, value)| CustomField {
+            name: name.clone(),
+            value: value_string(value),
+            hidden: matches!(value, Value::Protected(_)),
+        })
+        .collect();
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Login {
+        username: entry.get_username().unwrap_or_default().to_string(),
+        password: entry.get_password().unwrap_or_default().to_string(),
+        urls: entry
+            .get_url()
+            .filter(|url| !url.is_empty())
+            .map(|url| vec![url.to_string()])
+            .unwrap_or_default(),
+        notes: entry.get("Notes").unwrap_or_default().to_string(),
+        fields,
+    }
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::Unprotected(s) => s.clone(),
+        Value::Protected(s) => String::from_utf8_lossy(s.unsecure()).to_string(),
+        Value::Bytes(b) => String::from_utf8_lossy(b).to_string(),
+    }
+}
+
+fn timestamp(seconds: Option<i64>) -> Option<u64> {
+    seconds.and_then(|s| u64::try_from(s).ok())
+}
+
+fn base64_uuid(entry: &Entry) -> String {
+    base64::engine::general_purpose::STANDARD.encode(entry.uuid.as_bytes())
+}
+
+// KDBX 4 的附件在内部头中按顺序编号，KDBX 3.1 的附件在 Meta/Binaries 中按 ID 编号
+fn attachment_contents(db: &Database) -> HashMap<String, Vec<u8>> {
+    let mut contents = HashMap::new();
+    for (i, attachment) in db.header_attachments.iter().enumerate() {
+        contents.insert(i.to_string(), attachment.content.clone());
+    }
+    for (i, binary) in db.meta.binaries.binaries.iter().enumerate() {
+        let id = binary.identifier.clone().unwrap_or_else(|| i.to_string());
+        contents.insert(id, binary.content.clone());
+    }
+    contents
+}
+
+// 返回条目 UUID 到 (附件名, 引用编号) 的映射，跳过历史版本中的条目
+fn entry_binaries(xml: &[u8]) -> Result<HashMap<String, Vec<(String, String)>>, String> {
+    let mut reader = Reader::from_reader(xml);
+    let mut stack: Vec<String> = vec![];
+    let mut binaries: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    let mut uuid = String::new();
+    let mut key = String::new();
+    let mut refs: Vec<(String, String)> = vec![];
+
+    let in_entry = |stack: &[String], parent: &str| {
+        let n = stack.len();
+        n >= 2
+            && stack[n - 2] == parent
+            && stack.contains(&"Entry".to_string())
+            && !stack.contains(&"History".to_string())
+    };
+    let reference = |e: &BytesStart| -> Option<String> {
+        e.try_get_attribute("Ref")
+            .ok()
+            .flatten()
+            .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+    };
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                stack.push(String::from_utf8_lossy(e.local_name().as_ref()).to_string());
+                if stack.last().map(|s| s.as_str()) == Some("Value") && in_entry(&stack, "Binary") {
+                    if let Some(r) = reference(&e) {
+                        refs.push((key.clone(), r));
+                    }
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                stack.push(String::from_utf8_lossy(e.local_name().as_ref()).to_string());
+                if e.local_name().as_ref() == b"Value" && in_entry(&stack, "Binary") {
+                    if let Some(r) = reference(&e) {
+                        refs.push((key.clone(), r));
+                    }
+                }
+                stack.pop();
+            }
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|e| format!("Invalid KeePass XML: {}", e))?;
+                match stack.last().map(|s| s.as_str()) {
+                    Some("UUID") if in_entry(&stack, "Entry") => uuid = text.trim().to_string(),
+                    Some("Key") if in_entry(&stack, "Binary") => key = text.to_string(),
+                    _ => {}
+                }
+            }
+            Ok(Event::End(_)) => {
+                let name = stack.pop().unwrap_or_default();
+                if name == "Entry" && !stack.contains(&"History".to_string()) {
+                    if !refs.is_empty() {
+                        binaries.insert(uuid.clone(), std::mem::take(&mut refs));
+                    }
+                    uuid.clear();
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(format!("Invalid KeePass XML: {}", e)),
+        }
+    }
+    Ok(binaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str) -> Entry {
+        let mut entry = Entry::new();
+        entry
+            .fields
+            .insert("Title".to_string(), Value::Unprotected(title.to_string()));
+        entry
+    }
+
+    fn titles(found: &[(String, &Entry)]) -> Vec<String> {
+        found
+            .iter()
+            .map(|(folder, entry)| format!("{}|{}", folder, entry.get_title().unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn maps_groups_to_folders() {
+        let mut work = Group::new("Work");
+        let mut servers = Group::new("Servers/Prod");
+        servers.add_child(entry("db"));
+        work.add_child(entry("mail"));
+        work.add_child(servers);
+        let mut root = Group::new("Root");
+        root.add_child(entry("top"));
+        root.add_child(work);
+
+        let mut found = vec![];
+        collect_entries(&root, "", &Meta::default(), &mut found);
+        assert_eq!(
+            titles(&found),
+            vec!["|top", "Work|mail", "Work/Servers-Prod|db"]
+        );
+    }
+
+    #[test]
+    fn skips_recycle_bin() {
+        let mut trash = Group::new("Recycle Bin");
+        trash.add_child(entry("deleted"));
+        let mut root = Group::new("Root");
+        root.add_child(entry("kept"));
+        let meta = Meta {
+            recyclebin_uuid: Some(trash.uuid),
+            ..Default::default()
+        };
+        root.add_child(trash);
+
+        let mut found = vec![];
+        collect_entries(&root, "", &meta, &mut found);
+        assert_eq!(titles(&found), vec!["|kept"]);
+    }
+
+    #[test]
+    fn splits_standard_and_custom_fields() {
+        let mut entry = entry("GitHub");
+        for (name, value) in [("UserName", "alice"), ("URL", ""), ("Notes", "n")] {
+            entry
+                .fields
+                .insert(name.to_string(), Value::Unprotected(value.to_string()));
+        }
+        entry
+            .fields
+            .insert("Recovery".to_string(), Value::Unprotected("r".to_string()));
+        let login = entry_login(&entry);
+        assert_eq!(login.username, "alice");
+        assert!(login.urls.is_empty());
+        assert_eq!(login.notes, "n");
+        assert_eq!(login.fields.len(), 1);
+        assert_eq!(login.fields[0].name, "Recovery");
+        assert!(!login.fields[0].hidden);
+    }
+
+    #[test]
+    fn reads_binary_refs() {
+        let xml = br#"<KeePassFile><Root><Group>
+            <Entry>
+                <UUID>AAAAAAAAAAAAAAAAAAAAAA==</UUID>
+                <String><Key>Title</Key><Value>a</Value></String>
+                <Binary><Key>id.pdf</Key><Value Ref="0"/></Binary>
+                <Binary><Key>key &amp; notes.txt</Key><Value Ref="2"></Value></Binary>
+                <History>
+                    <Entry>
+                        <UUID>AAAAAAAAAAAAAAAAAAAAAA==</UUID>
+                        <Binary><Key>old.pdf</Key><Value Ref="1"/></Binary>
+                    </Entry>
+                </History>
+            </Entry>
+            <Entry>
+                <UUID>AQEBAQEBAQEBAQEBAQEBAQ==</UUID>
+                <String><Key>Title</Key><Value>b</Value></String>
+            </Entry>
+        </Group></Root></KeePassFile>"#;
+        let binaries = entry_binaries(xml).unwrap();
+        assert_eq!(binaries.len(), 1);
+        assert_eq!(
+            binaries["AAAAAAAAAAAAAAAAAAAAAA=="],
+            vec![
+                ("id.pdf".to_string(), "0".to_string()),
+                ("key & notes.txt".to_string(), "2".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::entry::Login;
+use crate::index::{IndexMeta, ListItem};
+use crate::{add_entry, commit_and_push, get_index_lines, write_index};
+use serde::Serialize;
+use std::fs;
+
+pub mod bitwarden;
+pub mod csv;
+pub mod keepass;
+pub mod onepassword;
+pub mod pass;
+pub mod passwords;
+pub mod vault;
+
+// 各导出格式解析后的统一记录，login 和 note 二选一
+#[derive(Debug, Default)]
+pub struct ImportRecord {
+    pub title: String,
+    pub login: Option<Login>,
+    pub note: Option<(String, String)>,
+    pub totp: Option<String>,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+// 待写入的条目，内容只保存在内存中
+pub struct PendingEntry {
+    pub app: String,
+    pub desc: String,
+    pub format: String,
+    pub data: Vec<u8>,
+    pub meta: IndexMeta,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ImportSummary {
+    pub imported: Vec<ListItem>,
+    pub attachments: usize,
+    pub skipped: Vec<String>,
+}
+
+// 逐条走 add_entry 写入，任何一条失败都回滚索引并删除已写入的文件，
+// 全部成功后只做一次提交
+pub fn write_entries(
+    email: &str,
+    entries: Vec<PendingEntry>,
+    answer: &str,
+    summary: &mut ImportSummary,
+) -> Result<(), String> {
+    let original = get_index_lines()?;
+    let mut written: Vec<i32> = vec![];
+    for mut entry in entries {
+        let result = add_entry(
+            email,
+            &entry.app,
+            &entry.desc,
+            &entry.format,
+            &entry.data,
+            entry.meta.clone(),
+            answer,
+        );
+        entry.data.fill(0);
+        match result {
+            Ok(file_number) => {
+                written.push(file_number);
+                summary.imported.push(ListItem {
+                    id: format!("{:03}", file_number),
+                    app: entry.app,
+                    desc: entry.desc,
+                    format: entry.format,
+                    meta: entry.meta,
+                });
+            }
+            Err(e) => {
+                for file_number in written {
+                    let _ = fs::remove_file(format!("./{:03}.gpg", file_number));
+                }
+                write_index(email, &original)?;
+                return Err(format!(
+                    "Import aborted at {}.{}: {}",
+                    entry.app, entry.desc, e
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn commit_import(
+    source: &str,
+    summary: &ImportSummary,
+    push_to_cloud: &str,
+) -> Result<(), String> {
+    if summary.imported.is_empty() {
+        return Ok(());
+    }
+    // 提交信息是明文，只记录文件编号和数量
+    let mut message = format!("import: {} entries from {}", summary.imported.len(), source);
+    if summary.attachments > 0 {
+        message.push_str(&format!(", {} attachments", summary.attachments));
+    }
+    if !summary.skipped.is_empty() {
+        message.push_str(&format!(", {} skipped", summary.skipped.len()));
+    }
+    message.push('\n');
+    for item in &summary.imported {
+        message.push_str(&format!("\n{}.gpg", item.id));
+    }
+    commit_and_push(&message, push_to_cloud)
+}
+
+// app 和 desc 只保留字母、数字和连字符，其余字符替换为连字符
+pub fn sanitize_name(value: &str, fallback: &str) -> String {
+    let mut name = String::new();
+    for c in value.trim().chars() {
+        if c.is_alphanumeric() {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name = name.trim_matches('-');
+    if name.is_empty() {
+        fallback.to_string()
+    } else {
+        name.to_string()
+    }
+}
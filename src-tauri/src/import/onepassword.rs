@@ -0,0 +1,226 @@
+use super::ImportRecord;
+use crate::entry::{CustomField, Login};
+use serde_json::Value;
+use std::io::{Cursor, Read};
+
+// 1PUX 是一个 zip 包，条目都在 export.data 中，直接在内存中解压
+pub fn parse(data: &[u8]) -> Result<Vec<ImportRecord>, String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(data)).map_err(|e| format!("Invalid 1PUX file: {}", e))?;
+    let mut content = String::new();
+    archive
+        .by_name("export.data")
+        .map_err(|e| format!("Invalid 1PUX file: {}", e))?
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Invalid 1PUX file: {}", e))?;
+    let export: Value =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid 1PUX file: {}", e))?;
+    content.clear();
+
+    let mut records = vec![];
+    for account in export["accounts"].as_array().into_iter().flatten() {
+        for vault in account["vaults"].as_array().into_iter().flatten() {
+            let vault_name = vault["attrs"]["name"].as_str().map(|s| s.to_string());
+            for item in vault["items"].as_array().into_iter().flatten() {
+                if let Some(record) = parse_item(item, vault_name.clone()) {
+                    records.push(record);
+                }
+            }
+        }
+    }
+    Ok(records)
+}
+
+fn parse_item(item: &Value, vault_name: Option<String>) -> Option<ImportRecord> {
+    let state = item["state"].as_str().unwrap_or("active");
+    if state == "trashed" {
+        return None;
+    }
+    let overview = &item["overview"];
+    let details = &item["details"];
+
+    let mut tags: Vec<String> = overview["tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.as_str().map(|s| s.to_string()))
+        .collect();
+    if state == "archived" {
+        tags.push("archived".to_string());
+    }
+    let mut record = ImportRecord {
+        title: text(&overview["title"]),
+        folder: vault_name,
+        tags,
+        created_at: item["createdAt"].as_u64(),
+        updated_at: item["updatedAt"].as_u64(),
+        ..Default::default()
+    };
+
+    let mut login = Login {
+        password: text(&details["password"]),
+        notes: text(&details["notesPlain"]),
+        ..Default::default()
+    };
+    let mut urls: Vec<String> = overview["urls"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|u| text(&u["url"]))
+        .collect();
+    urls.insert(0, text(&overview["url"]));
+    for url in urls {
+        if !url.is_empty() && !login.urls.contains(&url) {
+            login.urls.push(url);
+        }
+    }
+
+    for field in details["loginFields"].as_array().into_iter().flatten() {
+        let value = text(&field["value"]);
+        match field["designation"].as_str() {
+            Some("username") => login.username = value,
+            Some("password") => login.password = value,
+            _ if !value.is_empty() => login.fields.push(CustomField {
+                name: text(&field["name"]),
+                value,
+                hidden: field["fieldType"].as_str() == Some("P"),
+            }),
+            _ => {}
+        }
+    }
+
+    // 分区字段的值是单键对象，键表示类型，如 concealed、string、totp
+    for section in details["sections"].as_array().into_iter().flatten() {
+        for field in section["fields"].as_array().into_iter().flatten() {
+            let Some((kind, value)) = field["value"].as_object().and_then(|v| v.iter().next())
+            else {
+                continue;
+            };
+            let value = text(value);
+            if value.is_empty() {
+                continue;
+            }
+            if kind == "totp" {
+                record.totp = Some(value);
+                continue;
+            }
+            let name = match text(&field["title"]) {
+                title if title.is_empty() => text(&field["id"]),
+                title => title,
+            };
+            login.fields.push(CustomField {
+                name,
+                value,
+                hidden: kind == "concealed",
+            });
+        }
+    }
+
+    let is_note = login.username.is_empty()
+        && login.password.is_empty()
+        && login.urls.is_empty()
+        && login.fields.is_empty();
+    if is_note {
+        record.note = Some((".txt".to_string(), login.notes));
+    } else {
+        record.login = Some(login);
+    }
+    Some(record)
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn pux(export: &str) -> Vec<u8> {
+        let mut buffer = Cursor::new(vec![]);
+        let mut writer = zip::ZipWriter::new(&mut buffer);
+        writer
+            .start_file("export.data", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(export.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        buffer.into_inner()
+    }
+
+    const EXPORT: &str = r#"{ "accounts": [{ "vaults": [{
+        "attrs": { "name": "Personal" },
+        "items": [
+            {
+                "createdAt": 100, "updatedAt": 200,
+                "overview": {
+                    "title": "GitHub", "url": "https://github.com", "tags": ["dev"],
+                    "urls": [{ "url": "https://github.com" }, { "url": "https://gist.github.com" }]
+                },
+                "details": {
+                    "loginFields": [
+                        { "designation": "username", "value": "alice" },
+                        { "designation": "password", "value": "pw" }
+                    ],
+                    "sections": [{ "fields": [
+                        { "title": "", "id": "otp", "value": { "totp": "otpauth://totp/x?secret=JBSWY3DP" } },
+                        { "title": "Recovery", "value": { "concealed": "r-code" } }
+                    ] }]
+                }
+            },
+            {
+                "state": "archived",
+                "overview": { "title": "Memo" },
+                "details": { "notesPlain": "remember" }
+            },
+            { "state": "trashed", "overview": { "title": "Old" }, "details": {} }
+        ]
+    }] }] }"#;
+
+    #[test]
+    fn parses_export_data() {
+        let records = parse(&pux(EXPORT)).unwrap();
+        assert_eq!(records.len(), 2);
+
+        let github = &records[0];
+        let login = github.login.as_ref().unwrap();
+        assert_eq!(github.folder.as_deref(), Some("Personal"));
+        assert_eq!(github.tags, vec!["dev"]);
+        assert_eq!(
+            (github.created_at, github.updated_at),
+            (Some(100), Some(200))
+        );
+        assert_eq!(login.username, "alice");
+        assert_eq!(login.password, "pw");
+        assert_eq!(
+            login.urls,
+            vec!["https://github.com", "https://gist.github.com"]
+        );
+        assert_eq!(
+            github.totp.as_deref(),
+            Some("otpauth://totp/x?secret=JBSWY3DP")
+        );
+        assert_eq!(login.fields.len(), 1);
+        assert_eq!(login.fields[0].name, "Recovery");
+        assert!(login.fields[0].hidden);
+
+        assert!(records[1].login.is_none());
+        assert_eq!(records[1].tags, vec!["archived"]);
+        assert_eq!(
+            records[1].note,
+            Some((".txt".to_string(), "remember".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_missing_export_data() {
+        assert!(parse(b"not a zip")
+            .unwrap_err()
+            .starts_with("Invalid 1PUX file"));
+    }
+}
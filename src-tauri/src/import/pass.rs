@@ -0,0 +1,244 @@
+use super::{commit_import, sanitize_name, write_entries, ImportSummary, PendingEntry};
+use crate::index::{normalize_folder, IndexMeta};
+use crate::sync::lock_repo;
+use crate::{get_git_cmd, get_gpg_email, run_gpg_with_input, verify_security_question};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::command;
+
+// 每个 .gpg 文件解密后原样作为 .txt 条目重新加密，路径最后两级作为 app 和 desc，
+// 更上层的目录作为文件夹；只有一级时 desc 为 password
+#[command]
+pub async fn import_pass(
+    path: Option<String>,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<ImportSummary, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+
+    let store = match path {
+        Some(path) => PathBuf::from(path),
+        None => default_store()?,
+    };
+    if !store.is_dir() {
+        return Err(format!("Password store {} not found", store.display()));
+    }
+
+    let mut files = vec![];
+    collect_files(&store, &store, &mut files)?;
+    files.sort();
+    let history = history_dates(&store);
+
+    let mut summary = ImportSummary::default();
+    let mut pending = vec![];
+    for relative in files {
+        let name = relative.trim_end_matches(".gpg");
+        let content = match decrypt_file(&store.join(&relative)) {
+            Ok(content) => content,
+            Err(e) => {
+                summary.skipped.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+
+        let (app, desc, folder) = entry_names(name);
+        let dates = history.get(&relative);
+        pending.push(PendingEntry {
+            app,
+            desc,
+            format: ".txt".to_string(),
+            data: content,
+            meta: IndexMeta {
+                folder,
+                created_at: dates.map(|(created, _)| *created),
+                updated_at: dates.map(|(_, updated)| *updated),
+                ..Default::default()
+            },
+        });
+    }
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    write_entries(&email, pending, &answer, &mut summary)?;
+    commit_import("pass", &summary, &push_to_cloud)?;
+    Ok(summary)
+}
+
+// 返回 app、desc 和文件夹，name 是去掉 .gpg 后缀、以 / 分隔的相对路径
+fn entry_names(name: &str) -> (String, String, Option<String>) {
+    let mut parts: Vec<&str> = name.split('/').collect();
+    let last = parts.pop().unwrap_or_default();
+    let (app, desc) = match parts.pop() {
+        Some(parent) => (
+            sanitize_name(parent, "pass"),
+            sanitize_name(last, "password"),
+        ),
+        None => (sanitize_name(last, "pass"), "password".to_string()),
+    };
+    (
+        app,
+        desc,
+        normalize_folder(&parts.join("/")).unwrap_or(None),
+    )
+}
+
+fn default_store() -> Result<PathBuf, String> {
+    if let Ok(dir) = std::env::var("PASSWORD_STORE_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".password-store"))
+        .ok_or_else(|| "Failed to get home directory".to_string())
+}
+
+// 返回相对于仓库根目录、以 / 分隔的路径，跳过 .git 等隐藏目录
+fn collect_files(store: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(store, &path, files)?;
+        } else if name.ends_with(".gpg") {
+            let relative = path.strip_prefix(store).map_err(|e| e.to_string())?;
+            let parts: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+// pass 的文件只用 gpg 私钥加密，解密结果只保留在内存中
+fn decrypt_file(path: &Path) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let output = run_gpg_with_input(&["--quiet", "--batch", "--decrypt"], &data)?;
+    if !output.status.success() {
+        return Err(format!(
+            "Error result for gpg command: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+// 从 pass 仓库的 git 历史中取每个文件最早和最近一次提交的时间，
+// 不是 git 仓库时返回空表，由 add_entry 使用当前时间
+fn history_dates(store: &Path) -> HashMap<String, (u64, u64)> {
+    let dates = HashMap::new();
+    if !store.join(".git").exists() {
+        return dates;
+    }
+    let Ok(git) = get_git_cmd() else {
+        return dates;
+    };
+    let Ok(output) = Command::new(git)
+        .arg("-C")
+        .arg(store)
+        .args([
+            "-c",
+            "core.quotepath=off",
+            "log",
+            "--format=@%ct",
+            "--name-only",
+            "--no-renames",
+        ])
+        .output()
+    else {
+        return dates;
+    };
+    if !output.status.success() {
+        return dates;
+    }
+    parse_history(&String::from_utf8_lossy(&output.stdout))
+}
+
+// 日志从新到旧，第一次遇到的是最近修改时间，最后一次遇到的是创建时间
+fn parse_history(log: &str) -> HashMap<String, (u64, u64)> {
+    let mut dates: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut time = 0;
+    for line in log.lines() {
+        if let Some(secs) = line.strip_prefix('@') {
+            time = secs.parse().unwrap_or(0);
+        } else if line.ends_with(".gpg") {
+            dates
+                .entry(line.to_string())
+                .and_modify(|(created, _)| *created = time)
+                .or_insert((time, time));
+        }
+    }
+    dates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_store_paths() {
+        assert_eq!(
+            entry_names("github"),
+            ("github".to_string(), "password".to_string(), None)
+        );
+        assert_eq!(
+            entry_names("email/work"),
+            ("email".to_string(), "work".to_string(), None)
+        );
+        assert_eq!(
+            entry_names("personal/social/twitter/alice@example.com"),
+            (
+                "twitter".to_string(),
+                "alice-example-com".to_string(),
+                Some("personal/social".to_string())
+            )
+        );
+        assert_eq!(
+            entry_names("my bank/online login"),
+            ("my-bank".to_string(), "online-login".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn skips_hidden_directories() {
+        let store = std::env::temp_dir().join(format!("safesecrets-pass-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&store);
+        for dir in ["web", ".git/objects", ".extensions"] {
+            fs::create_dir_all(store.join(dir)).unwrap();
+        }
+        for file in [
+            "top.gpg",
+            "web/site.gpg",
+            "web/readme.txt",
+            ".gpg-id",
+            ".hidden.gpg",
+            ".git/objects/x.gpg",
+            ".extensions/ext.gpg",
+        ] {
+            fs::write(store.join(file), b"x").unwrap();
+        }
+        let mut files = vec![];
+        collect_files(&store, &store, &mut files).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["top.gpg", "web/site.gpg"]);
+        let _ = fs::remove_dir_all(&store);
+    }
+
+    #[test]
+    fn parses_history_dates() {
+        let log = "@300\n\nweb/site.gpg\n@200\n\ntop.gpg\nweb/site.gpg\n.gpg-id\n@100\n\ntop.gpg\n";
+        let dates = parse_history(log);
+        assert_eq!(dates.len(), 2);
+        assert_eq!(dates["web/site.gpg"], (200, 300));
+        assert_eq!(dates["top.gpg"], (100, 200));
+    }
+}
@@ -0,0 +1,338 @@
+use super::csv::CsvMapping;
+use super::{
+    bitwarden, commit_import, csv, onepassword, sanitize_name, write_entries, ImportRecord,
+    ImportSummary, PendingEntry,
+};
+use crate::entry::{encode_secret, login_meta, TypedSecret, LOGIN_FORMAT};
+use crate::index::{normalize_folder, validate_tag, IndexMeta, ListItem};
+use crate::otp::{index_kind, parse_otp};
+use crate::sync::lock_repo;
+use crate::{get_gpg_email, get_index_lines, verify_security_question};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use tauri::command;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ImportSource {
+    #[serde(rename = "bitwarden")]
+    Bitwarden,
+    #[serde(rename = "1pux")]
+    OnePux,
+    #[serde(rename = "csv")]
+    Csv,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PreviewItem {
+    app: String,
+    desc: String,
+    format: String,
+    folder: Option<String>,
+    tags: Vec<String>,
+    duplicate: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImportPreview {
+    items: Vec<PreviewItem>,
+    duplicates: usize,
+    skipped: Vec<String>,
+    summary: Option<ImportSummary>,
+}
+
+// app、desc、format 和文件夹都相同才是同一个条目
+type EntryKey = (String, String, String, Option<String>);
+
+fn entry_key(app: &str, desc: &str, format: &str, folder: &Option<String>) -> EntryKey {
+    (
+        app.to_string(),
+        desc.to_string(),
+        format.to_string(),
+        folder.clone(),
+    )
+}
+
+// 导出文件只在内存中解析，不落盘任何明文；dry_run 时只返回预览，
+// 与现有条目 app、desc、format、文件夹都相同的记录视为重复，不会导入
+#[command]
+pub async fn import_passwords(
+    path: String,
+    source: ImportSource,
+    mapping: Option<CsvMapping>,
+    dry_run: bool,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<ImportPreview, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+
+    let mut skipped = vec![];
+    let mut data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let records = match source {
+        ImportSource::Bitwarden => bitwarden::parse(&data, &mut skipped),
+        ImportSource::OnePux => onepassword::parse(&data),
+        ImportSource::Csv => csv::parse(&data, &mapping.unwrap_or_default(), &mut skipped),
+    };
+    data.fill(0);
+    let records = records?;
+
+    let existing: HashSet<EntryKey> = get_index_lines()?
+        .iter()
+        .map(|line| ListItem::parse(line))
+        .map(|item| entry_key(&item.app, &item.desc, &item.format, &item.meta.folder))
+        .collect();
+
+    let mut items = vec![];
+    let mut pending = vec![];
+    let mut taken: HashSet<EntryKey> = HashSet::new();
+    for record in records {
+        let entries = record_entries(record, &mut skipped)?;
+        for mut entry in reserve_names(entries, &mut taken) {
+            let key = entry_key(&entry.app, &entry.desc, &entry.format, &entry.meta.folder);
+            let duplicate = existing.contains(&key);
+            items.push(PreviewItem {
+                app: entry.app.clone(),
+                desc: entry.desc.clone(),
+                format: entry.format.clone(),
+                folder: entry.meta.folder.clone(),
+                tags: entry.meta.tags.clone(),
+                duplicate,
+            });
+            if duplicate {
+                entry.data.fill(0);
+            } else {
+                pending.push(entry);
+            }
+        }
+    }
+    let duplicates = items.iter().filter(|item| item.duplicate).count();
+
+    if dry_run {
+        for entry in pending.iter_mut() {
+            entry.data.fill(0);
+        }
+        return Ok(ImportPreview {
+            items,
+            duplicates,
+            skipped,
+            summary: None,
+        });
+    }
+
+    let source_name = match source {
+        ImportSource::Bitwarden => "bitwarden",
+        ImportSource::OnePux => "1password",
+        ImportSource::Csv => "csv",
+    };
+    let mut summary = ImportSummary {
+        skipped: skipped.clone(),
+        ..Default::default()
+    };
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    write_entries(&email, pending, &answer, &mut summary)?;
+    commit_import(source_name, &summary, &push_to_cloud)?;
+    Ok(ImportPreview {
+        items,
+        duplicates,
+        skipped,
+        summary: Some(summary),
+    })
+}
+
+// 同一文件中同名的记录加上 -2、-3 等后缀，同一记录的条目使用相同后缀。
+// 后缀只取决于文件中的顺序，重复导入时仍能识别出已有条目
+fn reserve_names(
+    mut entries: Vec<PendingEntry>,
+    taken: &mut HashSet<EntryKey>,
+) -> Vec<PendingEntry> {
+    let mut n = 1;
+    let keys = loop {
+        let suffix = if n == 1 {
+            String::new()
+        } else {
+            format!("-{}", n)
+        };
+        let keys: Vec<EntryKey> = entries
+            .iter()
+            .map(|entry| {
+                let desc = format!("{}{}", entry.desc, suffix);
+                entry_key(&entry.app, &desc, &entry.format, &entry.meta.folder)
+            })
+            .collect();
+        if keys.iter().all(|key| !taken.contains(key)) {
+            break keys;
+        }
+        n += 1;
+    };
+    for (entry, key) in entries.iter_mut().zip(keys) {
+        entry.desc = key.1.clone();
+        taken.insert(key);
+    }
+    entries
+}
+
+// 一条记录对应一个登录或笔记条目，带有 TOTP 时另外生成一个 .totp 条目，
+// 无法解析的 TOTP 记入 skipped
+fn record_entries(
+    record: ImportRecord,
+    skipped: &mut Vec<String>,
+) -> Result<Vec<PendingEntry>, String> {
+    let app = sanitize_name(&record.title, "import");
+    let mut base = IndexMeta {
+        folder: record
+            .folder
+            .as_deref()
+            .and_then(|folder| normalize_folder(folder).ok().flatten()),
+        tags: record
+            .tags
+            .iter()
+            .filter_map(|tag| validate_tag(tag).ok())
+            .collect(),
+        created_at: record.created_at,
+        updated_at: record.updated_at,
+        ..Default::default()
+    };
+    base.tags.sort();
+    base.tags.dedup();
+
+    let mut entries = vec![];
+    let mut desc = "note".to_string();
+    if let Some(login) = record.login {
+        desc = sanitize_name(&login.username, "login");
+        let meta = login_meta(&app, &login);
+        entries.push(PendingEntry {
+            app: app.clone(),
+            desc: desc.clone(),
+            format: LOGIN_FORMAT.to_string(),
+            data: encode_secret(&TypedSecret::Login(login))?.into_bytes(),
+            meta: IndexMeta {
+                kind: meta.kind,
+                urls: meta.urls,
+                strength: meta.strength,
+                breached: meta.breached,
+                ..base.clone()
+            },
+        });
+    } else if let Some((format, content)) = record.note {
+        entries.push(PendingEntry {
+            app: app.clone(),
+            desc: desc.clone(),
+            format,
+            data: content.into_bytes(),
+            meta: base.clone(),
+        });
+    }
+
+    let otp = match record.totp.as_deref().map(parse_otp) {
+        Some(Ok(otp)) => Some(otp),
+        Some(Err(e)) => {
+            skipped.push(format!("{}.{}: one-time password: {}", app, desc, e));
+            None
+        }
+        None => None,
+    };
+    if let Some(otp) = otp {
+        let (format, kind) = index_kind(&otp);
+        entries.push(PendingEntry {
+            app,
+            desc,
+            format: format.to_string(),
+            data: encode_secret(&TypedSecret::Totp(otp))?.into_bytes(),
+            meta: IndexMeta {
+                kind: kind.to_string(),
+                ..base
+            },
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::Login;
+
+    fn note(title: &str, folder: Option<&str>) -> ImportRecord {
+        ImportRecord {
+            title: title.to_string(),
+            note: Some((".txt".to_string(), "text".to_string())),
+            folder: folder.map(|f| f.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn names(records: Vec<ImportRecord>) -> Vec<String> {
+        let mut taken = HashSet::new();
+        let mut skipped = vec![];
+        let mut names = vec![];
+        for record in records {
+            let entries = record_entries(record, &mut skipped).unwrap();
+            for entry in reserve_names(entries, &mut taken) {
+                let folder = entry.meta.folder.unwrap_or_default();
+                names.push(format!(
+                    "{}/{}.{}{}",
+                    folder, entry.app, entry.desc, entry.format
+                ));
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn keeps_colliding_notes() {
+        let names = names(vec![
+            note("Wifi", None),
+            note("Wifi", None),
+            note("Wifi", Some("home")),
+            note("Wifi", None),
+        ]);
+        assert_eq!(
+            names,
+            vec![
+                "/Wifi.note.txt",
+                "/Wifi.note-2.txt",
+                "home/Wifi.note.txt",
+                "/Wifi.note-3.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn suffixes_whole_record() {
+        let login = |totp: Option<&str>| ImportRecord {
+            title: "GitHub".to_string(),
+            login: Some(Login {
+                username: "alice".to_string(),
+                password: "correct horse battery staple".to_string(),
+                ..Default::default()
+            }),
+            totp: totp.map(|t| t.to_string()),
+            ..Default::default()
+        };
+        let names = names(vec![login(None), login(Some("JBSWY3DP"))]);
+        assert_eq!(
+            names,
+            vec![
+                "/GitHub.alice.login",
+                "/GitHub.alice-2.login",
+                "/GitHub.alice-2.totp",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_otp() {
+        let record = ImportRecord {
+            totp: Some("otpauth://totp/x?secret=JBSWY3DP&digits=4".to_string()),
+            ..note("Bank", None)
+        };
+        let mut skipped = vec![];
+        let entries = record_entries(record, &mut skipped).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("Bank.note: one-time password: "));
+    }
+}
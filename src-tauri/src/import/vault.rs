@@ -0,0 +1,108 @@
+use super::{commit_import, write_entries, ImportSummary, PendingEntry};
+use crate::index::ListItem;
+use crate::migration::ensure_vault_supported;
+use crate::sync::lock_repo;
+use crate::{
+    decrypt_bytes, decrypt_file_with_passphrase, get_gpg_email, get_index_lines, hash, hash_twice,
+    split_index_lines, verify_security_question,
+};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+// 合并另一个 Safesecrets 仓库：用它的答案解密，再用当前仓库的收件人和答案重新加密，
+// 按当前仓库的编号依次追加；app、desc 都相同的条目视为重复并跳过，不论格式
+#[command]
+pub async fn import_vault(
+    path: String,
+    other_answer: String,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<ImportSummary, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+
+    let other = PathBuf::from(&path);
+    let current = env::current_dir().map_err(|e| format!("Failed to get current dir: {}", e))?;
+    if fs::canonicalize(&other).ok() == fs::canonicalize(&current).ok() {
+        return Err("Cannot merge a vault into itself".to_string());
+    }
+    if !other.join("000.gpg").exists() || !other.join("answer.gpg").exists() {
+        return Err(format!("{} is not a Safesecrets vault", path));
+    }
+    ensure_vault_supported(&other)?;
+
+    let stored = decrypt_bytes(&read(&other, "answer.gpg")?).map_err(explain_missing_key)?;
+    if String::from_utf8_lossy(&stored) != hash_twice(&other_answer) {
+        return Err("Invalid answer for the other vault".to_string());
+    }
+
+    let mut seen: HashSet<(String, String)> = get_index_lines()?
+        .iter()
+        .map(|line| ListItem::parse(line))
+        .map(|item| (item.app, item.desc))
+        .collect();
+
+    // 先在内存中解密全部条目，任何一条失败都不写入
+    let passphrase = hash(&other_answer);
+    let mut summary = ImportSummary::default();
+    let mut pending = vec![];
+    let index = decrypt_bytes(&read(&other, "000.gpg")?).map_err(explain_missing_key)?;
+    for line in split_index_lines(&index) {
+        let item = ListItem::parse(&line);
+        let key = (item.app.clone(), item.desc.clone());
+        if !seen.insert(key) {
+            summary.skipped.push(format!(
+                "{}.{}{}: duplicate",
+                item.app, item.desc, item.format
+            ));
+            continue;
+        }
+        let file = other.join(format!("{}.gpg", item.id));
+        let data = decrypt_file_with_passphrase(&file.to_string_lossy(), &passphrase)
+            .map_err(explain_missing_key)
+            .map_err(|e| format!("Failed to decrypt {}.gpg: {}", item.id, e))?;
+        pending.push(PendingEntry {
+            app: item.app,
+            desc: item.desc,
+            format: item.format,
+            data,
+            meta: item.meta,
+        });
+    }
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    write_entries(&email, pending, &answer, &mut summary)?;
+    commit_import("vault", &summary, &push_to_cloud)?;
+    Ok(summary)
+}
+
+fn read(dir: &Path, name: &str) -> Result<Vec<u8>, String> {
+    fs::read(dir.join(name)).map_err(|e| format!("Failed to read {}: {}", name, e))
+}
+
+// 另一个仓库加密给它自己的 GPG 密钥，本机钥匙串里没有对应私钥时 gpg 只报 No secret key
+fn explain_missing_key(e: String) -> String {
+    if e.contains("No secret key") {
+        "The GPG secret key of the other vault is not in this keyring, import it with `gpg --import` first".to_string()
+    } else {
+        e
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_missing_secret_key() {
+        let e = "Error result for gpg command: gpg: decryption failed: No secret key\n";
+        assert!(explain_missing_key(e.to_string()).contains("gpg --import"));
+        let e = "Error result for gpg command: gpg: decryption failed: Bad session key";
+        assert_eq!(explain_missing_key(e.to_string()), e);
+    }
+}
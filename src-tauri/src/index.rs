@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+// 索引行格式为 NNN.app.desc.format，附加信息以 JSON 形式放在制表符之后，
+// 旧版本写入的行没有附加信息。app 和 desc 中的 . 和 % 转义为 %2E 和 %25
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct IndexMeta {
+    #[serde(rename = "type", default, skip_serializing_if = "String::is_empty")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub urls: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strength: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breached: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_after_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl IndexMeta {
+    pub fn is_empty(&self) -> bool {
+        *self == IndexMeta::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ListItem {
+    pub id: String,
+    pub app: String,
+    pub desc: String,
+    pub format: String,
+    #[serde(flatten)]
+    pub meta: IndexMeta,
+}
+
+impl ListItem {
+    pub fn parse(line: &str) -> ListItem {
+        let (head, meta) = match line.split_once('\t') {
+            Some((head, meta)) => (head, serde_json::from_str(meta).unwrap_or_default()),
+            None => (line, IndexMeta::default()),
+        };
+        let parts: Vec<&str> = head.split('.').collect();
+        let part = |i: usize| parts.get(i).copied().unwrap_or("").to_string();
+        ListItem {
+            id: part(0),
+            app: unescape_name(&part(1)),
+            desc: unescape_name(&part(2)),
+            format: format!(".{}", parts.get(3..).unwrap_or(&[]).join(".")),
+            meta,
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        let head = format!(
+            "{}.{}.{}{}",
+            self.id,
+            escape_name(&self.app),
+            escape_name(&self.desc),
+            self.format
+        );
+        if self.meta.is_empty() {
+            return head;
+        }
+        match serde_json::to_string(&self.meta) {
+            Ok(meta) => format!("{}\t{}", head, meta),
+            Err(_) => head,
+        }
+    }
+
+    pub fn matches(&self, search_str: &str) -> bool {
+        let head = format!("{}.{}.{}{}", self.id, self.app, self.desc, self.format);
+        head.contains(search_str)
+            || self.meta.urls.iter().any(|url| url.contains(search_str))
+            || self.meta.tags.iter().any(|tag| tag.contains(search_str))
+    }
+
+    // 文件夹按路径前缀匹配，work 同时包含 work/aws 下的条目
+    pub fn in_folder(&self, folder: &str) -> bool {
+        let folder = folder.trim_matches('/');
+        match &self.meta.folder {
+            Some(path) => {
+                folder.is_empty() || path == folder || path.starts_with(&format!("{}/", folder))
+            }
+            None => folder.is_empty(),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.meta.tags.iter().any(|t| t == tag)
+    }
+}
+
+fn escape_name(value: &str) -> String {
+    value.replace('%', "%25").replace('.', "%2E")
+}
+
+// 旧版本写入的名字不含 .，只要其中没有 %2E 或 %25 就按原样解析
+fn unescape_name(value: &str) -> String {
+    value.replace("%2E", ".").replace("%25", "%")
+}
+
+// app 和 desc 写进索引行时会转义 .，但不能包含制表符和换行
+pub fn validate_name(field: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() || value.contains(['\t', '\n', '\r']) {
+        return Err(format!("Invalid {}: {}", field, value));
+    }
+    Ok(())
+}
+
+pub fn validate_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    if tag.is_empty() || tag.contains(['\t', '\n', '\r', ',']) {
+        return Err(format!("Invalid tag: {}", tag));
+    }
+    Ok(tag.to_string())
+}
+
+// 文件夹路径以 / 分隔，去掉首尾的 /，空路径表示不属于任何文件夹。
+// 导出时文件夹会变成目录，不允许 . 和 .. 这样的路径段
+pub fn normalize_folder(folder: &str) -> Result<Option<String>, String> {
+    let folder = folder.trim().trim_matches('/');
+    if folder.is_empty() {
+        return Ok(None);
+    }
+    let parts: Vec<&str> = folder.split('/').map(|part| part.trim()).collect();
+    if parts.iter().any(|part| {
+        part.is_empty() || *part == "." || *part == ".." || part.contains(['\t', '\n', '\r'])
+    }) {
+        return Err(format!("Invalid folder: {}", folder));
+    }
+    Ok(Some(parts.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_legacy_lines() {
+        let item = ListItem::parse("007.github.token.txt");
+        assert_eq!(item.id, "007");
+        assert_eq!(item.app, "github");
+        assert_eq!(item.desc, "token");
+        assert_eq!(item.format, ".txt");
+        assert!(item.meta.is_empty());
+
+        let item = ListItem::parse("008.aws.backup.tar.gz");
+        assert_eq!(item.format, ".tar.gz");
+    }
+
+    #[test]
+    fn parses_meta() {
+        let item = ListItem::parse("002.mail.login.json\t{\"type\":\"login\",\"tags\":[\"work\"]}");
+        assert_eq!(item.meta.kind, "login");
+        assert!(item.has_tag("work"));
+        assert_eq!(
+            item.to_line(),
+            "002.mail.login.json\t{\"type\":\"login\",\"tags\":[\"work\"]}"
+        );
+
+        let item = ListItem::parse("003.mail.login.json\tnot json");
+        assert!(item.meta.is_empty());
+    }
+
+    #[test]
+    fn round_trips_dots_in_names() {
+        let item = ListItem {
+            id: "001".to_string(),
+            app: "github.com".to_string(),
+            desc: "50%.off".to_string(),
+            format: ".txt".to_string(),
+            meta: IndexMeta::default(),
+        };
+        let line = item.to_line();
+        assert_eq!(line, "001.github%2Ecom.50%25%2Eoff.txt");
+        assert_eq!(ListItem::parse(&line), item);
+        assert!(item.matches("github.com"));
+    }
+
+    #[test]
+    fn validates_names() {
+        assert!(validate_name("app", "github.com").is_ok());
+        assert!(validate_name("app", "").is_err());
+        assert!(validate_name("desc", "a\tb").is_err());
+        assert!(validate_name("desc", "a\nb").is_err());
+    }
+
+    #[test]
+    fn normalizes_folders() {
+        assert_eq!(
+            normalize_folder("  /work/ aws /").unwrap(),
+            Some("work/aws".to_string())
+        );
+        assert_eq!(normalize_folder(" / ").unwrap(), None);
+        assert_eq!(
+            normalize_folder("a.b/.c").unwrap(),
+            Some("a.b/.c".to_string())
+        );
+        for folder in [
+            "work//aws",
+            "../etc",
+            "work/..",
+            "./work",
+            "work/ . ",
+            "a\tb",
+        ] {
+            assert!(normalize_folder(folder).is_err(), "{}", folder);
+        }
+    }
+
+    #[test]
+    fn matches_folder_prefix() {
+        let mut item = ListItem::parse("001.aws.root.txt");
+        assert!(item.in_folder(""));
+        assert!(!item.in_folder("work"));
+        item.meta.folder = Some("work/aws".to_string());
+        assert!(item.in_folder("work"));
+        assert!(item.in_folder("/work/aws/"));
+        assert!(!item.in_folder("wor"));
+    }
+}
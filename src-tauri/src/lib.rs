@@ -1,112 +1,76 @@
-use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::command;
 use tiny_keccak::Hasher;
 
+use index::{validate_name, IndexMeta, ListItem};
+
+mod attachment;
+mod backend;
+mod backup;
+mod emergency;
+mod entry;
+mod export;
+mod generator;
+mod import;
+mod index;
+mod migration;
+mod mirror;
+mod mnemonic;
+mod otp;
+mod recovery;
+mod rotation;
+mod runner;
+mod settings;
+mod strength;
+mod sync;
+mod tags;
+mod validate;
+
 #[command]
 async fn add_secrets(
     app: String,
     desc: String,
     format: String,
     secrets: String,
+    canonicalize: Option<bool>,
     push_to_cloud: String,
     answer: String,
 ) -> Result<(), String> {
-    let email = get_gpg_email().await?;
-    let mut lines = get_index_lines()?;
-    let mut file_number = 1;
-    if !lines.is_empty() {
-        let last_line = lines.last().unwrap();
-        let last_file_number_str = &last_line[..3];
-        file_number = last_file_number_str
-            .parse::<i32>()
-            .map_err(|e| e.to_string())?
-            + 1;
-    }
-    let file_number_str = format!("{:03}", file_number);
-    let new_line = format!("{}.{}.{}{}", file_number_str, app, desc, format);
-    lines.push(new_line);
-    let index = lines.join("\n");
-    encrypt(&email, &index, "./000.gpg")?;
+    // 按声明的格式校验内容，可选择保存规范化后的文本
+    let secrets = match validate::check_format(&format, &secrets) {
+        Ok(formatted) if canonicalize.unwrap_or(false) => formatted,
+        Ok(_) => secrets,
+        Err(e) => return Err(e.to_json()),
+    };
 
-    encrypt_with_answer(
+    let email = get_gpg_email().await?;
+    let _guard = sync::lock_repo();
+    let file_number = add_entry(
         &email,
-        &secrets,
+        &app,
+        &desc,
+        &format,
+        secrets.as_bytes(),
+        IndexMeta::default(),
         &answer,
-        &format!("./{}.gpg", file_number_str),
     )?;
 
     // 清空敏感缓存
     secrets.into_bytes().fill(0);
 
-    // Git 提交
-    let output = Command::new(get_git_cmd()?)
-        .args(["add", "./"])
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    if !output.status.success() {
-        return Err(format!(
-            "Error result for git command: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-
-    let commit_message = format!("add: {}.gpg", file_number);
-    let output = Command::new(get_git_cmd()?)
-        .args(["commit", "-m", &commit_message])
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    if !output.status.success() {
-        return Err(format!(
-            "Error result for git command: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-
-    // 推送到云端
-    if push_to_cloud == "yes" {
-        let _ = Command::new(get_git_cmd()?)
-            .args(["pull", "--rebase", "origin", "main"])
-            .env(
-                "GIT_SSH_COMMAND",
-                "ssh -o StrictHostKeyChecking=accept-new -o UserKnownHostsFile=/dev/null",
-            )
-            .output();
-
-        let mut args = vec!["push", "origin", "main"];
-        if !git_upstream_exists()? {
-            args = vec!["push", "-u", "origin", "main"];
-        }
-
-        let output = Command::new(get_git_cmd()?)
-            .args(args)
-            .env(
-                "GIT_SSH_COMMAND",
-                "ssh -o StrictHostKeyChecking=accept-new -o UserKnownHostsFile=/dev/null",
-            )
-            .output()
-            .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-        if !output.status.success() {
-            return Err(format!(
-                "Error result for git command: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-    }
-    Ok(())
+    commit_and_push(&format!("add: {}.gpg", file_number), &push_to_cloud)
 }
 
 #[command]
 async fn delete_secrets(id: String) -> Result<(), String> {
     let email = get_gpg_email().await?;
+    let _guard = sync::lock_repo();
     let mut lines = get_index_lines()?;
     lines.retain(|line| {
         if line.len() >= 3 {
@@ -133,50 +97,39 @@ async fn delete_secrets(id: String) -> Result<(), String> {
     encrypt(&email, &index, "./000.gpg")?;
 
     // Git 提交
-    let output = Command::new(get_git_cmd()?)
-        .args(["add", "./"])
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
-
-    if !output.status.success() {
-        return Err(format!(
-            "Error result for git command: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
+    git_commit(&format!("remove: {}.gpg", id))?;
 
-    let commit_message = format!("remove: {}.gpg", id);
-    let output = Command::new(get_git_cmd()?)
-        .args(["commit", "-m", &commit_message])
-        .output()
-        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+    Ok(())
+}
 
-    if !output.status.success() {
+#[command]
+async fn decrypt_secrets(id: String, answer: String) -> Result<String, String> {
+    if find_entry(&id)?.meta.kind == "file" {
         return Err(format!(
-            "Error result for git command: {}",
-            String::from_utf8_lossy(&output.stderr)
+            "Secrets {} is a file, use decrypt_file_secret instead",
+            id
         ));
     }
-
-    Ok(())
+    let data = decrypt_with_answer(&id, &answer)?;
+    Ok(String::from_utf8_lossy(&data).to_string())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ListItem {
-    id: String,
-    app: String,
-    desc: String,
-    format: String,
+// 先用安全问题答案做对称解密，再用 gpg 私钥做非对称解密
+fn decrypt_with_answer(id: &str, answer: &str) -> Result<Vec<u8>, String> {
+    decrypt_with_passphrase(id, &hash(answer))
 }
 
-#[command]
-async fn decrypt_secrets(id: String, answer: String) -> Result<String, String> {
+// passphrase 是答案的哈希，恢复套件还原出的也是它
+fn decrypt_with_passphrase(id: &str, passphrase: &str) -> Result<Vec<u8>, String> {
     let file = format!("./{}.gpg", id);
     if !Path::new(&file).exists() {
         return Err(format!("File {} not found", id));
     }
+    decrypt_file_with_passphrase(&file, passphrase)
+}
 
-    let passphrase = hash(&answer);
+// 也用于读取其他仓库中的文件
+fn decrypt_file_with_passphrase(file: &str, passphrase: &str) -> Result<Vec<u8>, String> {
     let child = Command::new(get_gpg_cmd()?)
         .args([
             "--quiet",
@@ -184,8 +137,8 @@ async fn decrypt_secrets(id: String, answer: String) -> Result<String, String> {
             "--batch",
             "--yes",
             "--passphrase",
-            &passphrase,
-            &file,
+            passphrase,
+            file,
         ])
         .stdout(std::process::Stdio::piped())
         .spawn()
@@ -204,22 +157,7 @@ async fn decrypt_secrets(id: String, answer: String) -> Result<String, String> {
 
     let symmetric_decrypted = &output.stdout;
 
-    let mut child = Command::new(get_gpg_cmd()?)
-        .args(["--quiet", "--decrypt"])
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to execute gpg command: {}", e))?;
-
-    if let Some(stdin) = child.stdin.as_mut() {
-        stdin
-            .write_all(symmetric_decrypted)
-            .map_err(|e| format!("Failed to write to gpg command: {}", e))?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for gpg command: {}", e))?;
+    let output = run_gpg_with_input(&["--quiet", "--decrypt"], symmetric_decrypted)?;
 
     if !output.status.success() {
         return Err(format!(
@@ -228,44 +166,42 @@ async fn decrypt_secrets(id: String, answer: String) -> Result<String, String> {
         ));
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    Ok(output.stdout)
 }
 
 #[command]
-async fn get_secrets_list(search_str: String, pull: bool) -> Result<Vec<ListItem>, String> {
+async fn get_secrets_list(
+    search_str: String,
+    pull: bool,
+    tag: Option<String>,
+    folder: Option<String>,
+) -> Result<Vec<ListItem>, String> {
     if pull {
-        let _ = Command::new(get_git_cmd()?)
-            .args(["pull", "--rebase", "origin", "main"])
-            .env(
-                "GIT_SSH_COMMAND",
-                "ssh -o StrictHostKeyChecking=accept-new -o UserKnownHostsFile=/dev/null",
-            )
-            .output();
+        // 错误已记录到同步状态中，不影响读取本地列表
+        let _ = sync::sync_repository();
     }
 
-    let lines = get_index_lines()?;
-    let mut items: Vec<ListItem> = lines
-        .iter()
-        .filter(|line| line.contains(&search_str))
-        .map(|line| {
-            let parts: Vec<&str> = line.split('.').collect();
-            let id = parts[0].to_string();
-            let app = parts[1].to_string();
-            let desc = parts[2].to_string();
-            let format = parts[3].to_string();
-            ListItem {
-                id,
-                app,
-                desc,
-                format: format!(".{}", format),
-            }
-        })
-        .collect();
-
+    let mut items = filter_items(&search_str, tag.as_deref(), folder.as_deref())?;
     items.reverse();
     Ok(items)
 }
 
+// 列表和清单导出共用同一套筛选条件
+pub fn filter_items(
+    search_str: &str,
+    tag: Option<&str>,
+    folder: Option<&str>,
+) -> Result<Vec<ListItem>, String> {
+    let lines = get_index_lines()?;
+    Ok(lines
+        .iter()
+        .map(|line| ListItem::parse(line))
+        .filter(|item| item.matches(search_str))
+        .filter(|item| tag.map_or(true, |tag| item.has_tag(tag)))
+        .filter(|item| folder.map_or(true, |folder| item.in_folder(folder)))
+        .collect())
+}
+
 #[command]
 async fn add_email_and_question(
     email: String,
@@ -292,6 +228,13 @@ async fn add_email_and_question(
 
 #[command]
 async fn verify_security_question(answer: String) -> Result<bool, String> {
+    if stored_answer_hash()? == hash_twice(&answer) {
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+fn stored_answer_hash() -> Result<String, String> {
     let output = Command::new(get_gpg_cmd()?)
         .args(["--quiet", "--decrypt", "./answer.gpg"])
         .output()
@@ -303,11 +246,7 @@ async fn verify_security_question(answer: String) -> Result<bool, String> {
             String::from_utf8_lossy(&output.stderr)
         ));
     }
-
-    if String::from_utf8_lossy(&output.stdout) == hash_twice(&answer) {
-        return Ok(true);
-    }
-    Ok(false)
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
 #[command]
@@ -363,10 +302,11 @@ async fn git_repository_exists() -> Result<bool, String> {
             String::from_utf8_lossy(&output.stderr)
         ));
     }
-    if output.stdout.is_empty() {
-        return Ok(false);
-    }
-    Ok(true)
+    // 镜像也是 git remote，这里只看主仓库 origin
+    let has_origin = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .any(|line| line.starts_with("origin\t"));
+    Ok(has_origin)
 }
 
 #[command]
@@ -410,7 +350,9 @@ async fn add_git_repository(repo: String) -> Result<(), String> {
     Ok(())
 }
 
+// 所有条目的读写都经过索引，版本过新的仓库在这里统一拒绝
 fn get_index_lines() -> Result<Vec<String>, String> {
+    migration::check_vault_version()?;
     if !Path::new("./000.gpg").exists() {
         return Ok(vec![]);
     }
@@ -427,13 +369,121 @@ fn get_index_lines() -> Result<Vec<String>, String> {
         ));
     }
 
-    let data = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<String> = data
+    Ok(split_index_lines(&output.stdout))
+}
+
+fn split_index_lines(data: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(data)
         .split('\n')
-        .filter(|s| s.len() > 0)
+        .filter(|s| !s.is_empty())
         .map(|s| s.to_string())
-        .collect();
-    Ok(lines)
+        .collect()
+}
+
+fn write_index(email: &str, lines: &[String]) -> Result<(), String> {
+    encrypt(email, &lines.join("\n"), "./000.gpg")
+}
+
+// 写入新条目的加密文件和索引，不做提交，返回文件编号
+fn add_entry(
+    email: &str,
+    app: &str,
+    desc: &str,
+    format: &str,
+    secrets: &[u8],
+    meta: IndexMeta,
+    answer: &str,
+) -> Result<i32, String> {
+    validate_name("app", app)?;
+    validate_name("desc", desc)?;
+
+    let mut lines = get_index_lines()?;
+    let mut file_number = 1;
+    if let Some(last_line) = lines.last() {
+        file_number = last_line[..3].parse::<i32>().map_err(|e| e.to_string())? + 1;
+    }
+    let now = now_secs();
+    let item = ListItem {
+        id: format!("{:03}", file_number),
+        app: app.to_string(),
+        desc: desc.to_string(),
+        format: format.to_string(),
+        meta: IndexMeta {
+            created_at: meta.created_at.or(Some(now)),
+            updated_at: meta.updated_at.or(Some(now)),
+            ..meta
+        },
+    };
+    lines.push(item.to_line());
+    write_index(email, &lines)?;
+
+    encrypt_with_answer(email, secrets, answer, &format!("./{}.gpg", item.id))?;
+    Ok(file_number)
+}
+
+// 更新已有条目的内容和附加信息，编号和位置不变
+fn update_entry(
+    email: &str,
+    id: &str,
+    secrets: &[u8],
+    meta: IndexMeta,
+    answer: &str,
+) -> Result<(), String> {
+    let mut lines = get_index_lines()?;
+    let line = lines
+        .iter_mut()
+        .find(|line| line.starts_with(&format!("{}.", id)))
+        .ok_or_else(|| format!("Secrets {} not found", id))?;
+    let mut item = ListItem::parse(line);
+    // 标签、文件夹、创建时间和轮换策略不随内容更新改变
+    item.meta = IndexMeta {
+        tags: std::mem::take(&mut item.meta.tags),
+        folder: item.meta.folder.take(),
+        created_at: item.meta.created_at,
+        updated_at: meta.updated_at.or(Some(now_secs())),
+        rotate_after_days: item.meta.rotate_after_days,
+        expires_at: item.meta.expires_at,
+        ..meta
+    };
+    *line = item.to_line();
+    write_index(email, &lines)?;
+
+    encrypt_with_answer(email, secrets, answer, &format!("./{}.gpg", id))
+}
+
+fn find_entry(id: &str) -> Result<ListItem, String> {
+    get_index_lines()?
+        .iter()
+        .map(|line| ListItem::parse(line))
+        .find(|item| item.id == id)
+        .ok_or_else(|| format!("Secrets {} not found", id))
+}
+
+fn commit_and_push(message: &str, push_to_cloud: &str) -> Result<(), String> {
+    let _guard = sync::lock_repo();
+    // Git 提交
+    git_commit(message)?;
+
+    // 推送到云端，失败时提交留在本地队列，下次同步时重试
+    if push_to_cloud == "yes" {
+        if let Err(e) = sync::sync_repository() {
+            log::warn!("Push deferred, will retry on next sync: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// 解密 gpg 非对称加密的数据
+fn decrypt_bytes(data: &[u8]) -> Result<Vec<u8>, String> {
+    let output = run_gpg_with_input(&["--quiet", "--decrypt"], data)?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Error result for gpg command: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(output.stdout)
 }
 
 fn encrypt(recipient: &str, message: &str, output_file: &str) -> Result<(), String> {
@@ -478,7 +528,7 @@ fn encrypt(recipient: &str, message: &str, output_file: &str) -> Result<(), Stri
 
 fn encrypt_with_answer(
     recipient: &str,
-    message: &str,
+    message: &[u8],
     answer: &str,
     output_file: &str,
 ) -> Result<(), String> {
@@ -488,22 +538,7 @@ fn encrypt_with_answer(
         }
     }
 
-    let mut child = Command::new(get_gpg_cmd()?)
-        .args(["--encrypt", "--recipient", recipient])
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to execute gpg command: {}", e))?;
-
-    if let Some(stdin) = child.stdin.as_mut() {
-        stdin
-            .write_all(message.as_bytes())
-            .map_err(|e| format!("Failed to write to gpg command: {}", e))?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for gpg command: {}", e))?;
+    let output = run_gpg_with_input(&["--encrypt", "--recipient", recipient], message)?;
 
     if !output.status.success() {
         return Err(format!(
@@ -551,6 +586,35 @@ fn encrypt_with_answer(
     Ok(())
 }
 
+// 输入和输出同时进行，避免大文件写满管道缓冲区后互相等待
+fn run_gpg_with_input(args: &[&str], input: &[u8]) -> Result<std::process::Output, String> {
+    let mut child = Command::new(get_gpg_cmd()?)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute gpg command: {}", e))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "Failed to open stdin of gpg command".to_string())?;
+    let (written, output) = std::thread::scope(|s| {
+        let writer = s.spawn(move || stdin.write_all(input));
+        let output = child.wait_with_output();
+        (writer.join(), output)
+    });
+
+    let output = output.map_err(|e| format!("Failed to wait for gpg command: {}", e))?;
+    if output.status.success() {
+        if let Ok(Err(e)) = written {
+            return Err(format!("Failed to write to gpg command: {}", e));
+        }
+    }
+    Ok(output)
+}
+
 static GPG_PATH_CACHE: OnceLock<Mutex<Option<String>>> = OnceLock::new();
 fn get_gpg_cmd() -> Result<String, String> {
     let cache = GPG_PATH_CACHE.get_or_init(|| Mutex::new(None));
@@ -730,6 +794,47 @@ fn git_upstream_exists() -> Result<bool, String> {
     Ok(false)
 }
 
+fn run_git(args: &[&str]) -> Result<String, String> {
+    let output = Command::new(get_git_cmd()?)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Error result for git command: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// 调用方应在写入文件之前就持有仓库锁，这里再取一次防止遗漏
+fn git_commit(message: &str) -> Result<(), String> {
+    let _guard = sync::lock_repo();
+    migration::check_vault_version()?;
+    migration::stamp_new_vault()?;
+    run_git(&["add", "./"])?;
+    run_git(&["commit", "-m", message])?;
+    Ok(())
+}
+
+// 仅本机使用的状态文件，放在 .git 下不会被提交和同步
+fn local_state_dir() -> Result<PathBuf, String> {
+    let dir = Path::new("./.git").join("safesecrets");
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create local state dir: {}", e))?;
+    }
+    Ok(dir)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 fn hash_twice(answer: &str) -> String {
     let mut hasher1 = tiny_keccak::Keccak::v256();
     let mut output1 = [0u8; 32];
@@ -752,6 +857,25 @@ fn hash(answer: &str) -> String {
     hex::encode(output1)
 }
 
+// 命令行模式，不启动界面，返回进程退出码
+pub fn run_cli() -> Option<i32> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(|arg| arg.as_str()) != Some("run") {
+        return None;
+    }
+    // Windows 发布版是 GUI 程序，启动时没有控制台，连接到调用它的终端才能读写
+    #[cfg(windows)]
+    unsafe {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+    if let Err(e) = start().and_then(|_| migration::check_vault_version()) {
+        eprintln!("{}", e);
+        return Some(1);
+    }
+    Some(runner::run_cli(&args[1..]))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -768,10 +892,68 @@ pub fn run() {
             is_gpg_available,
             exit_app,
             add_git_repository,
-            verify_security_question
+            verify_security_question,
+            sync::sync_status,
+            sync::sync_now,
+            sync::get_auto_sync,
+            sync::set_auto_sync,
+            sync::preview_pull,
+            sync::apply_pull,
+            entry::add_login,
+            entry::update_login,
+            entry::get_login_fields,
+            entry::get_secret_field,
+            otp::add_otp,
+            otp::get_otp_code,
+            mnemonic::validate_mnemonic,
+            mnemonic::add_mnemonic,
+            attachment::add_file_secret,
+            attachment::decrypt_file_secret,
+            generator::generate_password,
+            generator::generate_passphrase,
+            strength::check_password,
+            strength::set_breach_file,
+            strength::password_report,
+            strength::update_password_health,
+            validate::validate_secrets,
+            tags::set_entry_labels,
+            tags::list_tags,
+            tags::list_folders,
+            tags::rename_tag,
+            tags::merge_tags,
+            recovery::create_recovery_kit,
+            recovery::check_recovery_shares,
+            recovery::decrypt_with_recovery,
+            recovery::reset_answer_with_recovery,
+            rotation::set_rotation,
+            rotation::backfill_timestamps,
+            rotation::rotation_report,
+            runner::run_with_secrets,
+            backup::export_backup,
+            backup::restore_backup,
+            emergency::create_emergency_kit,
+            export::inventory::export_inventory,
+            export::pass::export_pass,
+            import::keepass::import_keepass,
+            import::pass::import_pass,
+            import::passwords::import_passwords,
+            import::vault::import_vault,
+            migration::vault_format,
+            migration::migrate_vault,
+            mirror::list_mirrors,
+            mirror::add_mirror,
+            mirror::remove_mirror,
+            mirror::resolve_mirror_conflicts
         ])
         .setup(|app| {
             start()?;
+            // 版本过新时不中断启动，由界面提示用户更新应用；读写条目和提交时会被拒绝
+            match migration::check_vault_version() {
+                Ok(()) => {
+                    tauri::async_runtime::spawn(sync::auto_sync_loop(app.handle().clone()));
+                }
+                Err(e) => log::warn!("{}", e),
+            }
             if cfg!(debug_assertions) {
                 app.handle().plugin(
                     tauri_plugin_log::Builder::default()
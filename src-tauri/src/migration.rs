@@ -0,0 +1,188 @@
+use crate::sync::{lock_repo, sync_repository};
+use crate::{git_commit, run_git, verify_security_question};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::command;
+
+// 仓库格式版本，索引格式、密钥派生或文件命名变化时加一并在 MIGRATIONS 中追加一步
+pub const VAULT_VERSION: u32 = 1;
+pub const VERSION_FILE: &str = "VERSION";
+
+// 没有 VERSION 文件的旧仓库视为版本 0
+struct Migration {
+    from: u32,
+    description: &'static str,
+    run: fn(&str) -> Result<(), String>,
+}
+
+// 按 from 从小到大排列，每一步把仓库从 from 升级到 from + 1
+const MIGRATIONS: [Migration; 1] = [Migration {
+    from: 0,
+    description: "Record the vault format version",
+    run: record_version_only,
+}];
+
+#[derive(Serialize, Debug)]
+pub struct VaultFormat {
+    version: u32,
+    supported: u32,
+    // ok、migrate（需要升级）或 too_new（需要更新应用）
+    status: &'static str,
+    pending: Vec<String>,
+}
+
+// 比应用新的仓库不自动同步，读取索引和提交时都会返回错误，界面通过 vault_format 提示更新应用；
+// 旧仓库等待用户确认后通过 migrate_vault 升级
+pub fn check_vault_version() -> Result<(), String> {
+    match read_version()? {
+        None => Ok(()),
+        Some(version) => ensure_supported(version, "Vault"),
+    }
+}
+
+// 新仓库在第一次提交时写入当前版本；启动时不写，
+// 以免未跟踪的 VERSION 挡住从已有远端仓库的首次拉取
+pub fn stamp_new_vault() -> Result<(), String> {
+    if read_version()?.is_none() {
+        write_version(VAULT_VERSION)?;
+    }
+    Ok(())
+}
+
+// 拉取前检查远端的版本，避免把新版本应用写入的数据合并进来
+pub fn check_remote_version(rev: &str) -> Result<(), String> {
+    let Ok(content) = run_git(&["show", &format!("{}:{}", rev, VERSION_FILE)]) else {
+        return Ok(());
+    };
+    ensure_supported(parse_version(&content)?, "Remote vault")
+}
+
+// 读取其他仓库时使用，没有 VERSION 的旧仓库同样视为版本 0
+pub fn ensure_vault_supported(dir: &Path) -> Result<(), String> {
+    match fs::read_to_string(dir.join(VERSION_FILE)) {
+        Ok(content) => ensure_supported(parse_version(&content)?, "Vault"),
+        Err(_) => Ok(()),
+    }
+}
+
+#[command]
+pub async fn vault_format() -> Result<VaultFormat, String> {
+    let version = read_version()?.unwrap_or(VAULT_VERSION);
+    let pending: Vec<String> = MIGRATIONS
+        .iter()
+        .filter(|m| m.from >= version)
+        .map(|m| format!("{} -> {}: {}", m.from, m.from + 1, m.description))
+        .collect();
+    let status = if version > VAULT_VERSION {
+        "too_new"
+    } else if !pending.is_empty() {
+        "migrate"
+    } else {
+        "ok"
+    };
+    Ok(VaultFormat {
+        version,
+        supported: VAULT_VERSION,
+        status,
+        pending,
+    })
+}
+
+// 升级前先提交未提交的改动作为还原点，每一步完成后单独提交；
+// 任何一步失败都回到升级前的提交
+#[command]
+pub async fn migrate_vault(push_to_cloud: String, answer: String) -> Result<VaultFormat, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+    let Some(version) = read_version()? else {
+        return vault_format().await;
+    };
+    ensure_supported(version, "Vault")?;
+    if version == VAULT_VERSION {
+        return vault_format().await;
+    }
+
+    {
+        let _guard = lock_repo();
+        if !run_git(&["status", "--porcelain"])?.trim().is_empty() {
+            git_commit(&format!(
+                "migrate: snapshot before vault format {}",
+                version
+            ))?;
+        }
+        let before = run_git(&["rev-parse", "--verify", "-q", "HEAD"])
+            .ok()
+            .map(|rev| rev.trim().to_string());
+
+        for migration in MIGRATIONS.iter().filter(|m| m.from >= version) {
+            let target = migration.from + 1;
+            let result = (migration.run)(&answer).and_then(|_| {
+                write_version(target)?;
+                git_commit(&format!(
+                    "migrate: vault format {} -> {}",
+                    migration.from, target
+                ))
+            });
+            if let Err(e) = result {
+                if let Some(rev) = &before {
+                    let _ = run_git(&["reset", "--hard", rev]);
+                    let _ = run_git(&["clean", "-fd"]);
+                }
+                return Err(format!(
+                    "Migration {} -> {} failed, vault restored: {}",
+                    migration.from, target, e
+                ));
+            }
+        }
+    }
+
+    if push_to_cloud == "yes" {
+        if let Err(e) = sync_repository() {
+            log::warn!("Push deferred, will retry on next sync: {}", e);
+        }
+    }
+    vault_format().await
+}
+
+// 返回 None 表示还没有任何提交的新仓库
+fn read_version() -> Result<Option<u32>, String> {
+    let path = Path::new("./").join(VERSION_FILE);
+    if path.exists() {
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read VERSION: {}", e))?;
+        return parse_version(&content).map(Some);
+    }
+    if run_git(&["rev-parse", "--verify", "-q", "HEAD"]).is_ok() {
+        return Ok(Some(0));
+    }
+    Ok(None)
+}
+
+fn parse_version(content: &str) -> Result<u32, String> {
+    content
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid vault format version: {}", content.trim()))
+}
+
+fn write_version(version: u32) -> Result<(), String> {
+    fs::write(Path::new("./").join(VERSION_FILE), format!("{}\n", version))
+        .map_err(|e| format!("Failed to write VERSION: {}", e))
+}
+
+fn ensure_supported(version: u32, what: &str) -> Result<(), String> {
+    if version > VAULT_VERSION {
+        return Err(format!(
+            "{} format version {} is newer than this app supports ({}), please update Safesecrets",
+            what, version, VAULT_VERSION
+        ));
+    }
+    Ok(())
+}
+
+// 版本 1 的文件布局与之前相同，只需要写入 VERSION
+fn record_version_only(_answer: &str) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use crate::backend::s3::S3Backend;
+use crate::backend::webdav::WebDavBackend;
+use crate::backend::{
+    read_secret, remove_local_state, secret_path, sync_backend, BackendSyncReport, Resolution,
+};
+use crate::settings::{read_settings, write_settings};
+use crate::sync::{lock_repo, read_state, run_git_remote};
+use crate::{encrypt, get_gpg_email, local_state_dir, now_secs, run_git};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::command;
+
+const BUNDLE_FILE: &str = "safesecrets.bundle";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorKind {
+    Git,
+    Directory,
+    WebDav,
+    S3,
+}
+
+// WebDAV 和 S3 需要的额外参数，密码和密钥单独加密保存
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MirrorOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Mirror {
+    pub name: String,
+    pub kind: MirrorKind,
+    pub target: String,
+    #[serde(flatten, default)]
+    pub options: MirrorOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct MirrorState {
+    last_push_at: Option<u64>,
+    last_error: Option<String>,
+    last_error_at: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MirrorStatus {
+    name: String,
+    kind: MirrorKind,
+    target: String,
+    primary: bool,
+    last_push_at: Option<u64>,
+    last_error: Option<String>,
+    last_error_at: Option<u64>,
+}
+
+#[command]
+pub async fn list_mirrors() -> Result<Vec<MirrorStatus>, String> {
+    let mut list = vec![];
+
+    // 主仓库 origin 排在第一位
+    if let Ok(url) = run_git(&["remote", "get-url", "origin"]) {
+        let state = read_state();
+        list.push(MirrorStatus {
+            name: "origin".to_string(),
+            kind: MirrorKind::Git,
+            target: url.trim().to_string(),
+            primary: true,
+            last_push_at: state.last_sync_at,
+            last_error: state.last_error,
+            last_error_at: state.last_error_at,
+        });
+    }
+
+    let states = read_mirror_states();
+    for mirror in read_settings().mirrors {
+        let state = states.get(&mirror.name).cloned().unwrap_or_default();
+        list.push(MirrorStatus {
+            name: mirror.name,
+            kind: mirror.kind,
+            target: mirror.target,
+            primary: false,
+            last_push_at: state.last_push_at,
+            last_error: state.last_error,
+            last_error_at: state.last_error_at,
+        });
+    }
+    Ok(list)
+}
+
+#[command]
+pub async fn add_mirror(
+    name: String,
+    kind: MirrorKind,
+    target: String,
+    options: Option<MirrorOptions>,
+    secret: Option<String>,
+) -> Result<(), String> {
+    if name.is_empty()
+        || name == "origin"
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid mirror name: {}", name));
+    }
+
+    let mut settings = read_settings();
+    if settings.mirrors.iter().any(|m| m.name == name) {
+        return Err(format!("Mirror {} already exists", name));
+    }
+    let options = options.unwrap_or_default();
+
+    match kind {
+        MirrorKind::Git => {
+            run_git(&["remote", "add", &name, &target])?;
+        }
+        MirrorKind::Directory => {
+            if !Path::new(&target).is_dir() {
+                return Err(format!("Directory {} not found", target));
+            }
+        }
+        MirrorKind::WebDav | MirrorKind::S3 => {
+            if !target.starts_with("http://") && !target.starts_with("https://") {
+                return Err(format!("Invalid URL: {}", target));
+            }
+            if kind == MirrorKind::S3 && (options.bucket.is_none() || options.username.is_none()) {
+                return Err("S3 mirror requires a bucket and an access key".to_string());
+            }
+            if let Some(secret) = &secret {
+                let email = get_gpg_email().await?;
+                let path = secret_path(&name)?;
+                encrypt(&email, secret, &path.to_string_lossy())?;
+            }
+        }
+    }
+
+    settings.mirrors.push(Mirror {
+        name,
+        kind,
+        target,
+        options,
+    });
+    write_settings(&settings)
+}
+
+#[command]
+pub async fn remove_mirror(name: String) -> Result<(), String> {
+    let mut settings = read_settings();
+    let mirror = settings
+        .mirrors
+        .iter()
+        .find(|m| m.name == name)
+        .cloned()
+        .ok_or_else(|| format!("Mirror {} not found", name))?;
+
+    match mirror.kind {
+        MirrorKind::Git => {
+            let _ = run_git(&["remote", "remove", &name]);
+        }
+        MirrorKind::WebDav | MirrorKind::S3 => remove_local_state(&name),
+        MirrorKind::Directory => {}
+    }
+    settings.mirrors.retain(|m| m.name != name);
+    write_settings(&settings)?;
+
+    let mut states = read_mirror_states();
+    states.remove(&name);
+    write_mirror_states(&states)
+}
+
+// WebDAV 和 S3 镜像上同一文件两边都被修改时，由用户选择保留本地还是远端的版本
+#[command]
+pub async fn resolve_mirror_conflicts(
+    name: String,
+    keep: Resolution,
+) -> Result<BackendSyncReport, String> {
+    let mirror = read_settings()
+        .mirrors
+        .into_iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("Mirror {} not found", name))?;
+    if !matches!(mirror.kind, MirrorKind::WebDav | MirrorKind::S3) {
+        return Err(format!("Mirror {} does not sync in both directions", name));
+    }
+
+    let _guard = lock_repo();
+    let result = sync_object_mirror(&mirror, Some(keep));
+    let mut states = read_mirror_states();
+    record_mirror_result(&mut states, &mirror.name, result.as_ref().err());
+    write_mirror_states(&states)?;
+    result
+}
+
+// 依次同步各个镜像，单个镜像失败只记录在该镜像的状态中
+pub fn sync_mirrors(mirrors: &[Mirror]) {
+    if mirrors.is_empty() {
+        return;
+    }
+
+    let mut states = read_mirror_states();
+    for mirror in mirrors {
+        let result = match mirror.kind {
+            MirrorKind::Git => push_git_mirror(mirror),
+            MirrorKind::Directory => write_directory_mirror(mirror),
+            MirrorKind::WebDav | MirrorKind::S3 => sync_object_mirror(mirror, None).map(|_| ()),
+        };
+        record_mirror_result(&mut states, &mirror.name, result.as_ref().err());
+    }
+
+    if let Err(e) = write_mirror_states(&states) {
+        log::warn!("{}", e);
+    }
+}
+
+fn record_mirror_result(
+    states: &mut HashMap<String, MirrorState>,
+    name: &str,
+    error: Option<&String>,
+) {
+    let state = states.entry(name.to_string()).or_default();
+    match error {
+        None => {
+            state.last_push_at = Some(now_secs());
+            state.last_error = None;
+            state.last_error_at = None;
+        }
+        Some(e) => {
+            log::warn!("Failed to push mirror {}: {}", name, e);
+            state.last_error = Some(e.clone());
+            state.last_error_at = Some(now_secs());
+        }
+    }
+}
+
+fn push_git_mirror(mirror: &Mirror) -> Result<(), String> {
+    run_git_remote(&["push", &mirror.name, "main"])?;
+    Ok(())
+}
+
+// WebDAV 和 S3 双向同步加密文件，下载的变更会在本地提交
+fn sync_object_mirror(
+    mirror: &Mirror,
+    resolution: Option<Resolution>,
+) -> Result<BackendSyncReport, String> {
+    let secret = if secret_path(&mirror.name)?.exists() {
+        Some(read_secret(&mirror.name)?)
+    } else {
+        None
+    };
+    let options = &mirror.options;
+
+    let report = match mirror.kind {
+        MirrorKind::WebDav => sync_backend(
+            &mirror.name,
+            &WebDavBackend::new(
+                &mirror.target,
+                options.username.as_deref(),
+                secret.as_deref(),
+            ),
+            resolution,
+        )?,
+        _ => sync_backend(
+            &mirror.name,
+            &S3Backend::new(
+                &mirror.target,
+                options.bucket.as_deref().unwrap_or(""),
+                options.prefix.as_deref(),
+                options.region.as_deref(),
+                options.username.as_deref().unwrap_or(""),
+                secret.as_deref().unwrap_or(""),
+            )?,
+            resolution,
+        )?,
+    };
+    log::info!(
+        "Synced {}: {} uploaded, {} downloaded",
+        mirror.name,
+        report.uploaded.len(),
+        report.downloaded.len()
+    );
+    Ok(report)
+}
+
+// 目录镜像写入一个 git bundle，校验通过后才替换旧文件
+fn write_directory_mirror(mirror: &Mirror) -> Result<(), String> {
+    let dir = Path::new(&mirror.target);
+    if !dir.is_dir() {
+        return Err(format!("Directory {} not found", mirror.target));
+    }
+
+    let tmp_file = dir.join(format!("{}.tmp", BUNDLE_FILE));
+    let tmp_path = tmp_file.to_string_lossy().to_string();
+    run_git(&["bundle", "create", &tmp_path, "main"])?;
+    if let Err(e) = run_git(&["bundle", "verify", &tmp_path]) {
+        let _ = fs::remove_file(&tmp_file);
+        return Err(e);
+    }
+
+    fs::rename(&tmp_file, dir.join(BUNDLE_FILE))
+        .map_err(|e| format!("Failed to write bundle to {}: {}", mirror.target, e))
+}
+
+fn read_mirror_states() -> HashMap<String, MirrorState> {
+    local_state_dir()
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join("mirrors.json")).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_mirror_states(states: &HashMap<String, MirrorState>) -> Result<(), String> {
+    let data = serde_json::to_string_pretty(states).map_err(|e| e.to_string())?;
+    fs::write(local_state_dir()?.join("mirrors.json"), data)
+        .map_err(|e| format!("Failed to write mirror state: {}", e))
+}
@@ -0,0 +1,277 @@
+use crate::entry::{encode_secret, TypedSecret};
+use crate::index::IndexMeta;
+use crate::sync::lock_repo;
+use crate::{add_entry, commit_and_push, get_gpg_email};
+use bip39::{Language, Mnemonic};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use tauri::command;
+
+pub const MNEMONIC_FORMAT: &str = ".mnemonic";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MnemonicSecret {
+    pub mnemonic: String,
+    pub language: String,
+    // BIP39 可选密码，即常说的第 25 个词
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub passphrase: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MnemonicCheck {
+    valid: bool,
+    language: Option<String>,
+    word_count: usize,
+    unknown_words: Vec<String>,
+    error: Option<String>,
+}
+
+#[command]
+pub async fn validate_mnemonic(
+    mnemonic: String,
+    language: Option<String>,
+) -> Result<MnemonicCheck, String> {
+    let (check, _) = check_mnemonic(&mnemonic, language.as_deref())?;
+    Ok(check)
+}
+
+#[command]
+pub async fn add_mnemonic(
+    app: String,
+    desc: String,
+    mnemonic: String,
+    passphrase: Option<String>,
+    language: Option<String>,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<(), String> {
+    let (check, normalized) = check_mnemonic(&mnemonic, language.as_deref())?;
+    let Some((words, language)) = normalized else {
+        return Err(match check.error {
+            Some(e) => format!("Invalid mnemonic: {}", e),
+            None => "Invalid mnemonic".to_string(),
+        });
+    };
+
+    let secret = MnemonicSecret {
+        mnemonic: words,
+        language: language_name(language).to_string(),
+        passphrase: passphrase.unwrap_or_default(),
+    };
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let payload = encode_secret(&TypedSecret::Mnemonic(secret))?;
+    let meta = IndexMeta {
+        kind: "mnemonic".to_string(),
+        ..Default::default()
+    };
+    let file_number = add_entry(
+        &email,
+        &app,
+        &desc,
+        MNEMONIC_FORMAT,
+        payload.as_bytes(),
+        meta,
+        &answer,
+    )?;
+    commit_and_push(&format!("add: {}.gpg", file_number), &push_to_cloud)
+}
+
+// 校验单词和校验和，有效时返回规范化后的助记词
+pub fn check_mnemonic(
+    input: &str,
+    language: Option<&str>,
+) -> Result<(MnemonicCheck, Option<(String, Language)>), String> {
+    let mut cow = Cow::Borrowed(input);
+    Mnemonic::normalize_utf8_cow(&mut cow);
+    let words: Vec<String> = cow.split_whitespace().map(|w| w.to_lowercase()).collect();
+    let normalized = words.join(" ");
+
+    let language = match language {
+        Some(name) => parse_language(name)?,
+        None => detect_language(&words),
+    };
+    let unknown_words: Vec<String> = words
+        .iter()
+        .filter(|w| language.find_word(w).is_none())
+        .cloned()
+        .collect();
+
+    let mut check = MnemonicCheck {
+        valid: false,
+        language: Some(language_name(language).to_string()),
+        word_count: words.len(),
+        unknown_words,
+        error: None,
+    };
+    match Mnemonic::parse_in_normalized(language, &normalized) {
+        Ok(_) => {
+            check.valid = true;
+            Ok((check, Some((normalized, language))))
+        }
+        Err(e) => {
+            check.error = Some(e.to_string());
+            Ok((check, None))
+        }
+    }
+}
+
+// 选择包含最多单词的词表，便于指出拼写错误的单词
+fn detect_language(words: &[String]) -> Language {
+    let mut best = Language::English;
+    let mut best_count = 0;
+    for language in Language::ALL {
+        let count = words
+            .iter()
+            .filter(|w| language.find_word(w).is_some())
+            .count();
+        if count > best_count {
+            best = *language;
+            best_count = count;
+        }
+    }
+    best
+}
+
+fn parse_language(name: &str) -> Result<Language, String> {
+    Language::ALL
+        .iter()
+        .find(|l| language_name(**l) == name.to_lowercase())
+        .copied()
+        .ok_or_else(|| format!("Unsupported mnemonic language: {}", name))
+}
+
+fn language_name(language: Language) -> &'static str {
+    match language {
+        Language::English => "english",
+        Language::SimplifiedChinese => "chinese-simplified",
+        Language::TraditionalChinese => "chinese-traditional",
+        Language::Czech => "czech",
+        Language::French => "french",
+        Language::Italian => "italian",
+        Language::Japanese => "japanese",
+        Language::Korean => "korean",
+        Language::Portuguese => "portuguese",
+        Language::Spanish => "spanish",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP-39 参考向量，密码为 TREZOR
+    const VECTORS: [(&str, &str); 4] = [
+        (
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        ),
+        (
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        ),
+        (
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8",
+        ),
+        (
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+        ),
+    ];
+
+    #[test]
+    fn accepts_reference_vectors() {
+        for (phrase, seed) in VECTORS {
+            let (check, normalized) = check_mnemonic(phrase, None).unwrap();
+            assert!(check.valid, "{}", phrase);
+            assert_eq!(check.language.as_deref(), Some("english"));
+            assert_eq!(check.word_count, 12);
+            let (words, language) = normalized.unwrap();
+            assert_eq!(words, phrase);
+            let mnemonic = Mnemonic::parse_in_normalized(language, &words).unwrap();
+            assert_eq!(hex::encode(mnemonic.to_seed("TREZOR")), seed);
+        }
+    }
+
+    #[test]
+    fn normalizes_case_and_spacing() {
+        let (check, normalized) = check_mnemonic(
+            "  Legal WINNER thank year wave sausage worth useful\nlegal winner thank  yellow ",
+            None,
+        )
+        .unwrap();
+        assert!(check.valid);
+        assert_eq!(normalized.unwrap().0, VECTORS[1].0);
+    }
+
+    #[test]
+    fn rejects_changed_word() {
+        // 最后一个词换成另一个有效单词，校验和不再匹配
+        let (check, normalized) = check_mnemonic(
+            "legal winner thank year wave sausage worth useful legal winner thank year",
+            None,
+        )
+        .unwrap();
+        assert!(!check.valid);
+        assert!(check.unknown_words.is_empty());
+        assert!(check.error.is_some());
+        assert!(normalized.is_none());
+
+        let (check, _) = check_mnemonic(
+            "legal winner thank year wave sausage worth useful legal winner thank yelow",
+            None,
+        )
+        .unwrap();
+        assert!(!check.valid);
+        assert_eq!(check.unknown_words, vec!["yelow".to_string()]);
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        let (check, _) = check_mnemonic("abandon abandon abandon", None).unwrap();
+        assert!(!check.valid);
+        assert_eq!(check.word_count, 3);
+    }
+
+    #[test]
+    fn accepts_other_wordlists() {
+        for language in Language::ALL {
+            let words = language.word_list();
+            let phrase = format!("{} {}", [words[0]; 11].join(" "), words[3]);
+            let (check, normalized) =
+                check_mnemonic(&phrase, Some(language_name(*language))).unwrap();
+            assert!(check.valid, "{}", language_name(*language));
+            assert!(normalized.is_some());
+        }
+    }
+
+    #[test]
+    fn detects_language() {
+        for language in [
+            Language::Spanish,
+            Language::French,
+            Language::Japanese,
+            Language::Korean,
+        ] {
+            let mnemonic = Mnemonic::from_entropy_in(language, &[0x7f; 16]).unwrap();
+            let (check, normalized) = check_mnemonic(&mnemonic.to_string(), None).unwrap();
+            assert!(check.valid);
+            assert_eq!(check.language.as_deref(), Some(language_name(language)));
+            assert_eq!(normalized.unwrap().1, language);
+        }
+
+        // 日文助记词通常用全角空格分隔
+        let mnemonic = Mnemonic::from_entropy_in(Language::Japanese, &[0x80; 16]).unwrap();
+        let phrase = mnemonic.words().collect::<Vec<_>>().join("\u{3000}");
+        let (check, _) = check_mnemonic(&phrase, None).unwrap();
+        assert!(check.valid);
+        assert_eq!(check.word_count, 12);
+    }
+
+    #[test]
+    fn rejects_unknown_language() {
+        assert!(check_mnemonic(VECTORS[0].0, Some("klingon")).is_err());
+    }
+}
@@ -0,0 +1,455 @@
+use crate::entry::{decrypt_typed, encode_secret, TypedSecret};
+use crate::index::IndexMeta;
+use crate::sync::lock_repo;
+use crate::{add_entry, commit_and_push, encrypt_with_answer, get_gpg_email, now_secs, run_git};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use tauri::command;
+
+pub const OTP_FORMAT: &str = ".totp";
+pub const HOTP_FORMAT: &str = ".hotp";
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpKind {
+    Totp,
+    Hotp,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Otp {
+    pub kind: OtpKind,
+    pub secret: String,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    #[serde(default)]
+    pub counter: u64,
+    #[serde(default)]
+    pub issuer: String,
+    #[serde(default)]
+    pub account: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OtpCode {
+    code: String,
+    seconds_remaining: Option<u64>,
+    period: Option<u64>,
+    counter: Option<u64>,
+}
+
+// 支持 otpauth:// URI 和纯 base32 种子
+#[command]
+pub async fn add_otp(
+    app: String,
+    desc: String,
+    uri: String,
+    push_to_cloud: String,
+    answer: String,
+) -> Result<(), String> {
+    let otp = parse_otp(&uri)?;
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let (format, kind) = index_kind(&otp);
+    let payload = encode_secret(&TypedSecret::Totp(otp))?;
+    let meta = IndexMeta {
+        kind: kind.to_string(),
+        ..Default::default()
+    };
+    let file_number = add_entry(
+        &email,
+        &app,
+        &desc,
+        format,
+        payload.as_bytes(),
+        meta,
+        &answer,
+    )?;
+    commit_and_push(&format!("add: {}.gpg", file_number), &push_to_cloud)
+}
+
+#[command]
+pub async fn get_otp_code(id: String, answer: String) -> Result<OtpCode, String> {
+    let TypedSecret::Totp(mut otp) = decrypt_typed(&id, &answer)? else {
+        return Err(format!("Secrets {} is not a one-time password", id));
+    };
+
+    match otp.kind {
+        OtpKind::Totp => {
+            let now = now_secs();
+            Ok(OtpCode {
+                code: generate_code(&otp, now / otp.period)?,
+                seconds_remaining: Some(otp.period - now % otp.period),
+                period: Some(otp.period),
+                counter: None,
+            })
+        }
+        OtpKind::Hotp => {
+            // HOTP 每次取码后计数器加一，只改写 NNN.gpg，索引和 updated_at 不变，
+            // 改动留在工作区，由 commit_counters 合并提交
+            let counter = otp.counter;
+            let code = generate_code(&otp, counter)?;
+            otp.counter += 1;
+
+            let email = get_gpg_email().await?;
+            let _guard = lock_repo();
+            let payload = encode_secret(&TypedSecret::Totp(otp))?;
+            encrypt_with_answer(
+                &email,
+                payload.as_bytes(),
+                &answer,
+                &format!("./{}.gpg", id),
+            )?;
+
+            Ok(OtpCode {
+                code,
+                seconds_remaining: None,
+                period: None,
+                counter: Some(counter),
+            })
+        }
+    }
+}
+
+// 索引中 TOTP 和 HOTP 分开记录，加密内容的类型标记仍然都是 totp
+pub fn index_kind(otp: &Otp) -> (&'static str, &'static str) {
+    match otp.kind {
+        OtpKind::Totp => (OTP_FORMAT, "totp"),
+        OtpKind::Hotp => (HOTP_FORMAT, "hotp"),
+    }
+}
+
+// 其他操作在释放仓库锁之前都会提交，此时工作区里未提交的 NNN.gpg 只可能是
+// HOTP 计数器，同步前把它们合并成一次提交；期间的其他提交也会顺带包含它们
+pub fn commit_counters() -> Result<(), String> {
+    let _guard = lock_repo();
+    let status = run_git(&["status", "--porcelain", "--untracked-files=no"])?;
+    let files: Vec<&str> = status
+        .lines()
+        .filter(|line| line.starts_with(" M "))
+        .map(|line| &line[3..])
+        .filter(|path| {
+            path.len() == 7
+                && path.ends_with(".gpg")
+                && path[..3].bytes().all(|b| b.is_ascii_digit())
+        })
+        .filter(|path| *path != "000.gpg")
+        .collect();
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    // 提交信息是明文，只记录文件编号
+    let mut message = format!("update: {} hotp counters\n", files.len());
+    for file in &files {
+        message.push_str(&format!("\n{}", file));
+    }
+    let mut args = vec!["commit", "-m", message.as_str(), "--"];
+    args.extend(files);
+    run_git(&args)?;
+    Ok(())
+}
+
+pub fn parse_otp(input: &str) -> Result<Otp, String> {
+    let input = input.trim();
+    let mut otp = Otp {
+        kind: OtpKind::Totp,
+        secret: String::new(),
+        algorithm: OtpAlgorithm::Sha1,
+        digits: 6,
+        period: 30,
+        counter: 0,
+        issuer: String::new(),
+        account: String::new(),
+    };
+
+    if let Some(rest) = input.strip_prefix("otpauth://") {
+        let (kind, rest) = rest
+            .split_once('/')
+            .ok_or_else(|| "Invalid otpauth URI".to_string())?;
+        otp.kind = match kind.to_lowercase().as_str() {
+            "totp" => OtpKind::Totp,
+            "hotp" => OtpKind::Hotp,
+            _ => return Err(format!("Unsupported otpauth type: {}", kind)),
+        };
+
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let label = percent_decode(label);
+        match label.split_once(':') {
+            Some((issuer, account)) => {
+                otp.issuer = issuer.trim().to_string();
+                otp.account = account.trim().to_string();
+            }
+            None => otp.account = label.trim().to_string(),
+        }
+
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            match key.to_lowercase().as_str() {
+                "secret" => otp.secret = value,
+                "issuer" => otp.issuer = value,
+                "algorithm" => {
+                    otp.algorithm = match value.to_uppercase().as_str() {
+                        "SHA1" => OtpAlgorithm::Sha1,
+                        "SHA256" => OtpAlgorithm::Sha256,
+                        "SHA512" => OtpAlgorithm::Sha512,
+                        _ => return Err(format!("Unsupported OTP algorithm: {}", value)),
+                    }
+                }
+                "digits" => {
+                    otp.digits = value
+                        .parse()
+                        .map_err(|_| format!("Invalid OTP digits: {}", value))?
+                }
+                "period" => {
+                    otp.period = value
+                        .parse()
+                        .map_err(|_| format!("Invalid OTP period: {}", value))?
+                }
+                "counter" => {
+                    otp.counter = value
+                        .parse()
+                        .map_err(|_| format!("Invalid OTP counter: {}", value))?
+                }
+                _ => {}
+            }
+        }
+    } else {
+        otp.secret = input.to_string();
+    }
+
+    otp.secret = otp
+        .secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    if base32_decode(&otp.secret)?.is_empty() {
+        return Err("OTP secret is empty".to_string());
+    }
+    if !(6..=10).contains(&otp.digits) {
+        return Err(format!("OTP digits must be 6-10, got {}", otp.digits));
+    }
+    if otp.period == 0 {
+        return Err("OTP period must be greater than 0".to_string());
+    }
+    Ok(otp)
+}
+
+pub fn to_uri(otp: &Otp) -> String {
+    let kind = match otp.kind {
+        OtpKind::Totp => "totp",
+        OtpKind::Hotp => "hotp",
+    };
+    let algorithm = match otp.algorithm {
+        OtpAlgorithm::Sha1 => "SHA1",
+        OtpAlgorithm::Sha256 => "SHA256",
+        OtpAlgorithm::Sha512 => "SHA512",
+    };
+    let label = if otp.issuer.is_empty() {
+        percent_encode(&otp.account)
+    } else {
+        format!(
+            "{}:{}",
+            percent_encode(&otp.issuer),
+            percent_encode(&otp.account)
+        )
+    };
+    let mut uri = format!(
+        "otpauth://{}/{}?secret={}&algorithm={}&digits={}",
+        kind, label, otp.secret, algorithm, otp.digits
+    );
+    match otp.kind {
+        OtpKind::Totp => uri.push_str(&format!("&period={}", otp.period)),
+        OtpKind::Hotp => uri.push_str(&format!("&counter={}", otp.counter)),
+    }
+    if !otp.issuer.is_empty() {
+        uri.push_str(&format!("&issuer={}", percent_encode(&otp.issuer)));
+    }
+    uri
+}
+
+// RFC 4226 动态截断
+pub fn generate_code(otp: &Otp, counter: u64) -> Result<String, String> {
+    let key = base32_decode(&otp.secret)?;
+    let message = counter.to_be_bytes();
+    let digest = match otp.algorithm {
+        OtpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(&key, &message),
+        OtpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(&key, &message),
+        OtpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(&key, &message),
+    };
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = ((digest[offset] as u64 & 0x7f) << 24)
+        | ((digest[offset + 1] as u64) << 16)
+        | ((digest[offset + 2] as u64) << 8)
+        | (digest[offset + 3] as u64);
+    let code = binary % 10u64.pow(otp.digits);
+    Ok(format!("{:0width$}", code, width = otp.digits as usize))
+}
+
+fn hmac_digest<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <M as hmac::digest::KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn base32_decode(input: &str) -> Result<Vec<u8>, String> {
+    let mut output = vec![];
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&x| x == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("Invalid base32 character in OTP secret: {}", c as char))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(output)
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        output.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => output.push(b'%'),
+                }
+            }
+            b'+' => output.push(b' '),
+            b => output.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&output).to_string()
+}
+
+fn percent_encode(input: &str) -> String {
+    let mut output = String::new();
+    for b in input.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'@' => {
+                output.push(b as char)
+            }
+            _ => output.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED_SHA1: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+    const SEED_SHA256: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA";
+    const SEED_SHA512: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNA";
+
+    fn otp(kind: OtpKind, secret: &str, algorithm: OtpAlgorithm, digits: u32) -> Otp {
+        Otp {
+            kind,
+            secret: secret.to_string(),
+            algorithm,
+            digits,
+            period: 30,
+            counter: 0,
+            issuer: String::new(),
+            account: String::new(),
+        }
+    }
+
+    // RFC 4226 附录 D
+    #[test]
+    fn matches_hotp_vectors() {
+        let hotp = otp(OtpKind::Hotp, SEED_SHA1, OtpAlgorithm::Sha1, 6);
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(generate_code(&hotp, counter as u64).unwrap(), *code);
+        }
+    }
+
+    // RFC 6238 附录 B
+    #[test]
+    fn matches_totp_vectors() {
+        let vectors: [(u64, &str, &str, &str); 6] = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        let sha1 = otp(OtpKind::Totp, SEED_SHA1, OtpAlgorithm::Sha1, 8);
+        let sha256 = otp(OtpKind::Totp, SEED_SHA256, OtpAlgorithm::Sha256, 8);
+        let sha512 = otp(OtpKind::Totp, SEED_SHA512, OtpAlgorithm::Sha512, 8);
+        for (time, code1, code256, code512) in vectors {
+            assert_eq!(generate_code(&sha1, time / 30).unwrap(), code1);
+            assert_eq!(generate_code(&sha256, time / 30).unwrap(), code256);
+            assert_eq!(generate_code(&sha512, time / 30).unwrap(), code512);
+        }
+    }
+
+    #[test]
+    fn parses_otpauth_uri() {
+        let otp = parse_otp(
+            "otpauth://hotp/ACME%20Co:alice@example.com?secret=jbsw-y3dp&algorithm=SHA256&digits=8&counter=5",
+        )
+        .unwrap();
+        assert_eq!(otp.kind, OtpKind::Hotp);
+        assert_eq!(otp.issuer, "ACME Co");
+        assert_eq!(otp.account, "alice@example.com");
+        assert_eq!(otp.secret, "JBSWY3DP");
+        assert_eq!(otp.algorithm, OtpAlgorithm::Sha256);
+        assert_eq!(otp.digits, 8);
+        assert_eq!(otp.counter, 5);
+        assert_eq!(index_kind(&otp), (HOTP_FORMAT, "hotp"));
+
+        let again = parse_otp(&to_uri(&otp)).unwrap();
+        assert_eq!(again.issuer, otp.issuer);
+        assert_eq!(again.counter, otp.counter);
+        assert_eq!(again.secret, otp.secret);
+    }
+
+    #[test]
+    fn rejects_invalid_otp() {
+        assert!(parse_otp("").is_err());
+        assert!(parse_otp("not base32!").is_err());
+        assert!(parse_otp("otpauth://totp/x?secret=JBSWY3DP&digits=4").is_err());
+        assert!(parse_otp("otpauth://totp/x?secret=JBSWY3DP&algorithm=MD5").is_err());
+        assert!(parse_otp("otpauth://totp/x?secret=JBSWY3DP&period=0").is_err());
+        assert!(parse_otp("otpauth://motp/x?secret=JBSWY3DP").is_err());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::index::ListItem;
+use crate::otp::commit_counters;
+use crate::sync::lock_repo;
+use crate::{
+    commit_and_push, decrypt_with_passphrase, encrypt, encrypt_with_answer, find_entry,
+    get_gpg_email, get_index_lines, hash, hash_twice, run_git, stored_answer_hash,
+    verify_security_question,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use tauri::command;
+use tiny_keccak::Hasher;
+
+// 分片格式 SSR1-套件编号-门限-序号-分片-校验，全部大写便于生成紧凑的二维码
+const SHARE_PREFIX: &str = "SSR1";
+const KEY_LEN: usize = 32;
+
+#[derive(Serialize, Debug)]
+pub struct RecoveryKit {
+    kit_id: String,
+    threshold: u8,
+    shares: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RecoveryCheck {
+    kit_id: String,
+    threshold: u8,
+    provided: usize,
+    valid: bool,
+}
+
+struct Share {
+    kit_id: String,
+    threshold: u8,
+    x: u8,
+    y: Vec<u8>,
+}
+
+// 拆分的是答案的哈希，即加密 NNN.gpg 的对称口令，分片不会泄露答案原文；
+// 重置答案后旧的恢复套件随之失效
+#[command]
+pub async fn create_recovery_kit(
+    threshold: u8,
+    shares: u8,
+    answer: String,
+) -> Result<RecoveryKit, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+    if threshold < 2 || shares < threshold {
+        return Err("Threshold must be at least 2 and not greater than shares".to_string());
+    }
+
+    let mut key = answer_key(&answer);
+    let mut id = [0u8; 4];
+    getrandom::fill(&mut id).map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+    let kit_id = hex::encode_upper(id);
+
+    let points = split(&key, threshold, shares)?;
+    key.fill(0);
+    let shares = points
+        .into_iter()
+        .map(|(x, y)| encode_share(&kit_id, threshold, x, &y))
+        .collect();
+    Ok(RecoveryKit {
+        kit_id,
+        threshold,
+        shares,
+    })
+}
+
+// 分片不足门限时返回 valid 为 false，不报错，便于界面逐个录入
+#[command]
+pub async fn check_recovery_shares(shares: Vec<String>) -> Result<RecoveryCheck, String> {
+    let parsed = parse_shares(&shares)?;
+    let first = &parsed[0];
+    let mut check = RecoveryCheck {
+        kit_id: first.kit_id.clone(),
+        threshold: first.threshold,
+        provided: parsed.len(),
+        valid: false,
+    };
+    if parsed.len() >= first.threshold as usize {
+        check.valid = recover_passphrase(&shares).is_ok();
+    }
+    Ok(check)
+}
+
+#[command]
+pub async fn decrypt_with_recovery(id: String, shares: Vec<String>) -> Result<String, String> {
+    if find_entry(&id)?.meta.kind == "file" {
+        return Err(format!(
+            "Secrets {} is a file, use decrypt_file_secret instead",
+            id
+        ));
+    }
+    let passphrase = recover_passphrase(&shares)?;
+    let data = decrypt_with_passphrase(&id, &passphrase)?;
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+// 用恢复出的口令解密所有条目，再用新答案重新加密。条目、answer.gpg 和
+// question.gpg 先全部写到 .new 临时文件，全部成功后才逐个替换原文件；
+// 替换中途失败时从 HEAD 恢复工作区，不会留下新旧答案混用的保管库
+#[command]
+pub async fn reset_answer_with_recovery(
+    shares: Vec<String>,
+    question: Option<String>,
+    new_answer: String,
+    push_to_cloud: String,
+) -> Result<usize, String> {
+    if new_answer.is_empty() {
+        return Err("New answer must not be empty".to_string());
+    }
+    let passphrase = recover_passphrase(&shares)?;
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    // 恢复工作区依赖 HEAD，先提交未提交的 HOTP 计数器
+    commit_counters()?;
+
+    let ids: Vec<String> = get_index_lines()?
+        .iter()
+        .map(|line| ListItem::parse(line).id)
+        .collect();
+    let mut files: Vec<String> = ids.iter().map(|id| format!("{}.gpg", id)).collect();
+    files.push("answer.gpg".to_string());
+    if question.is_some() {
+        files.push("question.gpg".to_string());
+    }
+    let temp_file = |file: &str| format!("./{}.new", file);
+    let remove_temp_files = || {
+        for file in &files {
+            let _ = fs::remove_file(temp_file(file));
+        }
+    };
+
+    for id in &ids {
+        let result = decrypt_with_passphrase(id, &passphrase).and_then(|mut data| {
+            let result = encrypt_with_answer(
+                &email,
+                &data,
+                &new_answer,
+                &temp_file(&format!("{}.gpg", id)),
+            );
+            data.fill(0);
+            result
+        });
+        if let Err(e) = result {
+            remove_temp_files();
+            return Err(format!("Failed to re-encrypt {}.gpg: {}", id, e));
+        }
+    }
+    let result =
+        encrypt(&email, &hash_twice(&new_answer), &temp_file("answer.gpg")).and_then(|_| {
+            match &question {
+                Some(question) => encrypt(&email, question, &temp_file("question.gpg")),
+                None => Ok(()),
+            }
+        });
+    if let Err(e) = result {
+        remove_temp_files();
+        return Err(e);
+    }
+
+    for file in &files {
+        if let Err(e) = fs::rename(temp_file(file), format!("./{}", file)) {
+            let restored = run_git(&["checkout", "HEAD", "--", "."]);
+            remove_temp_files();
+            return Err(match restored {
+                Ok(_) => format!("Failed to replace {}: {}", file, e),
+                Err(restore_error) => format!(
+                    "Failed to replace {}: {}; restoring the vault from HEAD also failed: {}",
+                    file, e, restore_error
+                ),
+            });
+        }
+    }
+
+    commit_and_push(
+        &format!(
+            "update: reset security answer\n\n{} files re-encrypted",
+            ids.len()
+        ),
+        &push_to_cloud,
+    )?;
+    Ok(ids.len())
+}
+
+// 还原出口令后与 answer.gpg 中保存的二次哈希比对，确认分片属于当前答案
+fn recover_passphrase(shares: &[String]) -> Result<String, String> {
+    let parsed = parse_shares(shares)?;
+    let threshold = parsed[0].threshold as usize;
+    if parsed.len() < threshold {
+        return Err(format!(
+            "At least {} shares are required, got {}",
+            threshold,
+            parsed.len()
+        ));
+    }
+
+    let mut key = combine(&parsed[..threshold]);
+    let mut hasher = tiny_keccak::Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(&key);
+    hasher.finalize(&mut output);
+    let passphrase = hex::encode(&key);
+    key.fill(0);
+
+    if hex::encode(output) != stored_answer_hash()? {
+        return Err("Recovery shares do not match the current security answer".to_string());
+    }
+    Ok(passphrase)
+}
+
+fn answer_key(answer: &str) -> Vec<u8> {
+    hex::decode(hash(answer)).unwrap_or_default()
+}
+
+fn encode_share(kit_id: &str, threshold: u8, x: u8, y: &[u8]) -> String {
+    let body = format!(
+        "{}-{}-{}-{}-{}",
+        SHARE_PREFIX,
+        kit_id,
+        threshold,
+        x,
+        hex::encode_upper(y)
+    );
+    let check = hex::encode_upper(&Sha256::digest(body.as_bytes())[..2]);
+    format!("{}-{}", body, check)
+}
+
+// 校验每个分片的格式和校验码，要求同一套件、门限一致且序号不重复
+fn parse_shares(shares: &[String]) -> Result<Vec<Share>, String> {
+    let mut parsed: Vec<Share> = vec![];
+    for (i, share) in shares.iter().enumerate() {
+        let share = share.trim().to_uppercase();
+        if share.is_empty() {
+            continue;
+        }
+        let invalid = || format!("Invalid recovery share #{}", i + 1);
+        let (body, check) = share.rsplit_once('-').ok_or_else(invalid)?;
+        if hex::encode_upper(&Sha256::digest(body.as_bytes())[..2]) != check {
+            return Err(format!("Recovery share #{} has a typo", i + 1));
+        }
+        let parts: Vec<&str> = body.split('-').collect();
+        if parts.len() != 5 || parts[0] != SHARE_PREFIX {
+            return Err(invalid());
+        }
+        let parsed_share = Share {
+            kit_id: parts[1].to_string(),
+            threshold: parts[2].parse().map_err(|_| invalid())?,
+            x: parts[3].parse().map_err(|_| invalid())?,
+            y: hex::decode(parts[4]).map_err(|_| invalid())?,
+        };
+        if parsed_share.x == 0 || parsed_share.y.len() != KEY_LEN {
+            return Err(invalid());
+        }
+        if let Some(first) = parsed.first() {
+            if first.kit_id != parsed_share.kit_id || first.threshold != parsed_share.threshold {
+                return Err(format!(
+                    "Recovery share #{} belongs to a different kit",
+                    i + 1
+                ));
+            }
+        }
+        if parsed.iter().any(|s| s.x == parsed_share.x) {
+            return Err(format!("Recovery share #{} is a duplicate", i + 1));
+        }
+        parsed.push(parsed_share);
+    }
+    if parsed.is_empty() {
+        return Err("No recovery shares provided".to_string());
+    }
+    Ok(parsed)
+}
+
+// GF(256) 上的 Shamir 秘密共享，逐字节生成 threshold-1 次随机多项式
+fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<(u8, Vec<u8>)>, String> {
+    let mut coefficients = vec![0u8; secret.len() * (threshold as usize - 1)];
+    getrandom::fill(&mut coefficients)
+        .map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+
+    let degree = threshold as usize - 1;
+    let mut points = vec![];
+    for x in 1..=shares {
+        let mut y = Vec::with_capacity(secret.len());
+        for (i, byte) in secret.iter().enumerate() {
+            // 霍纳法则，从最高次项开始，常数项是秘密本身
+            let mut acc = 0u8;
+            for c in coefficients[i * degree..(i + 1) * degree].iter().rev() {
+                acc = gf_mul(acc, x) ^ c;
+            }
+            y.push(gf_mul(acc, x) ^ byte);
+        }
+        points.push((x, y));
+    }
+    coefficients.fill(0);
+    Ok(points)
+}
+
+// 拉格朗日插值求 x = 0 处的值
+fn combine(shares: &[Share]) -> Vec<u8> {
+    let mut secret = vec![0u8; KEY_LEN];
+    for (i, share) in shares.iter().enumerate() {
+        let mut basis = 1u8;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_div(other.x, other.x ^ share.x));
+            }
+        }
+        for (byte, y) in secret.iter_mut().zip(&share.y) {
+            *byte ^= gf_mul(*y, basis);
+        }
+    }
+    secret
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+// a / b = a * b^254，b 不为 0
+fn gf_div(a: u8, b: u8) -> u8 {
+    let mut inverse = 1u8;
+    for _ in 0..254 {
+        inverse = gf_mul(inverse, b);
+    }
+    gf_mul(a, inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shares_of(points: &[(u8, Vec<u8>)], picked: &[usize]) -> Vec<Share> {
+        picked
+            .iter()
+            .map(|&i| Share {
+                kit_id: "TEST".to_string(),
+                threshold: picked.len() as u8,
+                x: points[i].0,
+                y: points[i].1.clone(),
+            })
+            .collect()
+    }
+
+    // 按位掩码枚举 n 个分片的所有子集
+    fn subsets(n: usize, k: usize) -> Vec<Vec<usize>> {
+        (0u32..1 << n)
+            .filter(|mask| mask.count_ones() as usize == k)
+            .map(|mask| (0..n).filter(|i| mask & (1 << i) != 0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn multiplies_in_gf256() {
+        // FIPS-197 4.2 节的例子
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        // 0x53 与 0xca 互为逆元
+        assert_eq!(gf_div(1, 0x53), 0xca);
+        assert_eq!(gf_div(1, 0xca), 0x53);
+        for b in 1..=255u8 {
+            assert_eq!(gf_mul(b, gf_div(1, b)), 1, "{}", b);
+        }
+    }
+
+    #[test]
+    fn combines_every_threshold_subset() {
+        let secret: Vec<u8> = (0..KEY_LEN as u8)
+            .map(|i| i.wrapping_mul(37) ^ 0xa5)
+            .collect();
+        for n in 2..=6u8 {
+            for k in 2..=n {
+                let points = split(&secret, k, n).unwrap();
+                assert_eq!(points.len(), n as usize);
+                for picked in subsets(n as usize, k as usize) {
+                    assert_eq!(
+                        combine(&shares_of(&points, &picked)),
+                        secret,
+                        "{}-of-{} {:?}",
+                        k,
+                        n,
+                        picked
+                    );
+                }
+                // 少于门限的分片还原不出秘密
+                let partial = shares_of(&points, &(0..k as usize - 1).collect::<Vec<_>>());
+                if partial.len() > 1 {
+                    assert_ne!(combine(&partial), secret);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_encoded_shares() {
+        let y = vec![0xabu8; KEY_LEN];
+        let shares = vec![
+            encode_share("CA241A39", 2, 1, &y),
+            format!("  {}\n", encode_share("CA241A39", 2, 2, &y).to_lowercase()),
+            String::new(),
+        ];
+        let parsed = parse_shares(&shares).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].kit_id, "CA241A39");
+        assert_eq!(parsed[1].x, 2);
+        assert_eq!(parsed[1].y, y);
+    }
+
+    #[test]
+    fn rejects_typos_and_mixed_shares() {
+        let y = vec![0x5au8; KEY_LEN];
+        let share = encode_share("CA241A39", 2, 1, &y);
+
+        // 改动正文中的任意一个字符都会被校验码发现
+        let typo = share.replacen("5A5A", "5A5B", 1);
+        assert_ne!(typo, share);
+        let error = parse_shares(&[typo]).err().unwrap();
+        assert!(error.contains("typo"), "{}", error);
+
+        let other_kit = encode_share("00000000", 2, 2, &y);
+        let error = parse_shares(&[share.clone(), other_kit]).err().unwrap();
+        assert!(error.contains("different kit"), "{}", error);
+
+        let error = parse_shares(&[share.clone(), share.clone()]).err().unwrap();
+        assert!(error.contains("duplicate"), "{}", error);
+
+        assert!(parse_shares(&[encode_share("CA241A39", 2, 0, &y)]).is_err());
+        assert!(parse_shares(&[encode_share("CA241A39", 2, 1, &y[1..])]).is_err());
+        assert!(parse_shares(&[share.replacen("SSR1", "SSR2", 1)]).is_err());
+        assert!(parse_shares(&[String::new()]).is_err());
+    }
+}
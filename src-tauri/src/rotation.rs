@@ -0,0 +1,245 @@
+use crate::index::ListItem;
+use crate::sync::lock_repo;
+use crate::{commit_and_push, get_gpg_email, get_index_lines, now_secs, run_git, write_index};
+use serde::Serialize;
+use tauri::command;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+// 这些提交会重写 NNN.gpg 但不代表内容被轮换：HOTP 计数器、重置答案后的重新加密、
+// 镜像同步和格式升级，推算更新时间时跳过
+const NON_ROTATING_COMMITS: [&str; 4] = [
+    "^update: [0-9]+ hotp counters$",
+    "^update: reset security answer$",
+    "^sync: ",
+    "^migrate: ",
+];
+
+#[derive(Serialize, Debug)]
+pub struct RotationItem {
+    #[serde(flatten)]
+    item: ListItem,
+    due_at: u64,
+    status: String,
+}
+
+#[command]
+pub async fn set_rotation(
+    id: String,
+    rotate_after_days: Option<u32>,
+    expires_at: Option<u64>,
+    push_to_cloud: String,
+) -> Result<(), String> {
+    if rotate_after_days == Some(0) {
+        return Err("Rotation interval must be at least 1 day".to_string());
+    }
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let mut lines = get_index_lines()?;
+    let line = lines
+        .iter_mut()
+        .find(|line| line.starts_with(&format!("{}.", id)))
+        .ok_or_else(|| format!("Secrets {} not found", id))?;
+    let mut item = ListItem::parse(line);
+    item.meta.rotate_after_days = rotate_after_days;
+    item.meta.expires_at = expires_at;
+    *line = item.to_line();
+
+    write_index(&email, &lines)?;
+    commit_and_push(&format!("update: {}.gpg rotation", id), &push_to_cloud)
+}
+
+// 旧条目没有时间信息，用最近一次新增该文件的提交时间作为创建时间，
+// 最后一次修改该文件的提交时间作为更新时间
+#[command]
+pub async fn backfill_timestamps(push_to_cloud: String) -> Result<usize, String> {
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let mut lines = get_index_lines()?;
+    let filled = fill_missing_timestamps(&mut lines)?;
+    if filled > 0 {
+        write_index(&email, &lines)?;
+        commit_and_push("update: backfill timestamps", &push_to_cloud)?;
+    }
+    Ok(filled)
+}
+
+// 列出已过轮换期、已过期或将在 within_days 天内到期的条目，
+// 没有单独设置轮换周期的条目使用 policy_days
+#[command]
+pub async fn rotation_report(
+    within_days: Option<u32>,
+    policy_days: Option<u32>,
+) -> Result<Vec<RotationItem>, String> {
+    // 旧条目的时间只在内存中补齐，写回索引由 backfill_timestamps 显式完成
+    let mut lines = get_index_lines()?;
+    fill_missing_timestamps(&mut lines)?;
+
+    let now = now_secs();
+    let soon = now + within_days.unwrap_or(14) as u64 * DAY_SECS;
+    let mut report = vec![];
+    for line in &lines {
+        let item = ListItem::parse(line);
+        let Some((due_at, status)) = rotation_status(&item, now, soon, policy_days) else {
+            continue;
+        };
+        report.push(RotationItem {
+            item,
+            due_at,
+            status: status.to_string(),
+        });
+    }
+
+    report.sort_by_key(|r| r.due_at);
+    Ok(report)
+}
+
+// 返回到期时间和状态，不在报告范围内时返回 None
+fn rotation_status(
+    item: &ListItem,
+    now: u64,
+    soon: u64,
+    policy_days: Option<u32>,
+) -> Option<(u64, &'static str)> {
+    let mut due: Vec<(u64, &str, &str)> = vec![];
+    if let Some(expires_at) = item.meta.expires_at {
+        due.push((expires_at, "expired", "expiring"));
+    }
+    let rotate_after = item.meta.rotate_after_days.or(policy_days);
+    let last_changed = item.meta.updated_at.or(item.meta.created_at);
+    if let (Some(days), Some(changed)) = (rotate_after, last_changed) {
+        due.push((changed + days as u64 * DAY_SECS, "overdue", "due_soon"));
+    }
+
+    // 同时有过期时间和轮换周期时取更早的一个
+    let (due_at, past, upcoming) = due.into_iter().min_by_key(|(at, _, _)| *at)?;
+    if due_at <= now {
+        Some((due_at, past))
+    } else if due_at <= soon {
+        Some((due_at, upcoming))
+    } else {
+        None
+    }
+}
+
+fn fill_missing_timestamps(lines: &mut [String]) -> Result<usize, String> {
+    let mut filled = 0;
+    for line in lines.iter_mut() {
+        let mut item = ListItem::parse(line);
+        if item.meta.created_at.is_some() && item.meta.updated_at.is_some() {
+            continue;
+        }
+        let file = format!("{}.gpg", item.id);
+        let created = commit_time(&["log", "-1", "--diff-filter=A", "--format=%ct", "--", &file])?;
+        let mut args = vec!["log", "-1", "--format=%ct", "--invert-grep", "-E"];
+        for pattern in NON_ROTATING_COMMITS {
+            args.extend(["--grep", pattern]);
+        }
+        args.extend(["--", &file]);
+        let updated = commit_time(&args)?;
+        if created.is_none() && updated.is_none() {
+            continue;
+        }
+        item.meta.created_at = item.meta.created_at.or(created).or(updated);
+        item.meta.updated_at = item.meta.updated_at.or(updated).or(created);
+        *line = item.to_line();
+        filled += 1;
+    }
+    Ok(filled)
+}
+
+fn commit_time(args: &[&str]) -> Result<Option<u64>, String> {
+    Ok(run_git(args)?.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexMeta;
+
+    const NOW: u64 = 1_000 * DAY_SECS;
+    const SOON: u64 = NOW + 14 * DAY_SECS;
+
+    fn item(meta: IndexMeta) -> ListItem {
+        ListItem {
+            id: "001".to_string(),
+            app: "github".to_string(),
+            desc: "token".to_string(),
+            format: ".txt".to_string(),
+            meta,
+        }
+    }
+
+    fn status(meta: IndexMeta, policy_days: Option<u32>) -> Option<(u64, &'static str)> {
+        rotation_status(&item(meta), NOW, SOON, policy_days)
+    }
+
+    fn changed(days_ago: u64, rotate_after_days: Option<u32>) -> IndexMeta {
+        IndexMeta {
+            created_at: Some(0),
+            updated_at: Some(NOW - days_ago * DAY_SECS),
+            rotate_after_days,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_rotation_due() {
+        assert_eq!(
+            status(changed(100, Some(90)), None),
+            Some((NOW - 10 * DAY_SECS, "overdue"))
+        );
+        assert_eq!(
+            status(changed(80, Some(90)), None),
+            Some((NOW + 10 * DAY_SECS, "due_soon"))
+        );
+        assert_eq!(status(changed(10, Some(90)), None), None);
+        // 到期时间恰好是现在时已经逾期
+        assert_eq!(status(changed(90, Some(90)), None), Some((NOW, "overdue")));
+    }
+
+    #[test]
+    fn falls_back_to_policy_and_created_at() {
+        assert_eq!(status(changed(100, None), None), None);
+        assert_eq!(
+            status(changed(100, None), Some(30)),
+            Some((NOW - 70 * DAY_SECS, "overdue"))
+        );
+        // 条目自己的周期优先于默认策略
+        assert_eq!(status(changed(100, Some(365)), Some(30)), None);
+
+        let created_only = IndexMeta {
+            created_at: Some(NOW - 5 * DAY_SECS),
+            ..Default::default()
+        };
+        assert_eq!(
+            status(created_only, Some(10)),
+            Some((NOW + 5 * DAY_SECS, "due_soon"))
+        );
+        assert_eq!(status(IndexMeta::default(), Some(10)), None);
+    }
+
+    #[test]
+    fn reports_expiry() {
+        let expires = |at| IndexMeta {
+            expires_at: Some(at),
+            ..Default::default()
+        };
+        assert_eq!(status(expires(NOW - 1), None), Some((NOW - 1, "expired")));
+        assert_eq!(status(expires(SOON), None), Some((SOON, "expiring")));
+        assert_eq!(status(expires(SOON + 1), None), None);
+
+        // 取过期时间和轮换到期中更早的一个
+        let both = IndexMeta {
+            expires_at: Some(NOW + DAY_SECS),
+            ..changed(100, Some(90))
+        };
+        assert_eq!(status(both, None), Some((NOW - 10 * DAY_SECS, "overdue")));
+        let both = IndexMeta {
+            expires_at: Some(NOW - DAY_SECS),
+            ..changed(80, Some(90))
+        };
+        assert_eq!(status(both, None), Some((NOW - DAY_SECS, "expired")));
+    }
+}
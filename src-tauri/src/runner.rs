@@ -0,0 +1,332 @@
+use crate::validate::check_format;
+use crate::{decrypt_with_answer, find_entry, verify_security_question};
+use serde::Serialize;
+use std::io::{BufRead, IsTerminal, Read};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::command;
+use zeroize::Zeroize;
+
+// 界面中运行的程序没有终端可以交互，超时后结束；输出只保留开头部分
+const RUN_TIMEOUT_SECS: u64 = 300;
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+#[derive(Serialize, Debug)]
+pub struct RunResult {
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    truncated: bool,
+}
+
+// 解密条目后以环境变量的形式传给子进程，变量不写入磁盘也不进入当前进程的环境
+#[command]
+pub async fn run_with_secrets(
+    id: String,
+    program: String,
+    args: Vec<String>,
+    answer: String,
+) -> Result<RunResult, String> {
+    if !verify_security_question(answer.clone()).await? {
+        return Err("Invalid answer".to_string());
+    }
+
+    let vars = load_env_vars(&id, &answer)?;
+    tauri::async_runtime::spawn_blocking(move || run_captured(&program, &args, vars))
+        .await
+        .map_err(|e| format!("Failed to run program: {}", e))?
+}
+
+// 在阻塞线程中运行，等待子进程结束或超时
+fn run_captured(
+    program: &str,
+    args: &[String],
+    mut vars: Vec<(String, String)>,
+) -> Result<RunResult, String> {
+    let child = Command::new(program)
+        .args(args)
+        .envs(vars.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    scrub(&mut vars);
+    let mut child = child.map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    let stdout = read_capped(child.stdout.take());
+    let stderr = read_capped(child.stderr.take());
+    let deadline = Instant::now() + Duration::from_secs(RUN_TIMEOUT_SECS);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                // 子进程启动的其他进程可能仍持有管道，不等待读取线程
+                return Err(format!(
+                    "{} did not finish within {} seconds and was stopped",
+                    program, RUN_TIMEOUT_SECS
+                ));
+            }
+            Err(e) => return Err(format!("Failed to wait for {}: {}", program, e)),
+        }
+    };
+
+    let (stdout, stdout_truncated) = stdout.join().unwrap_or_default();
+    let (stderr, stderr_truncated) = stderr.join().unwrap_or_default();
+    Ok(RunResult {
+        exit_code: status.code(),
+        stdout: String::from_utf8_lossy(&stdout).to_string(),
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+        truncated: stdout_truncated || stderr_truncated,
+    })
+}
+
+// 超出上限的部分继续读取并丢弃，避免子进程因管道写满而阻塞
+fn read_capped<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<(Vec<u8>, bool)> {
+    thread::spawn(move || {
+        let mut kept = vec![];
+        let mut truncated = false;
+        let Some(mut pipe) = pipe else {
+            return (kept, truncated);
+        };
+        let mut buf = [0u8; 8192];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let room = MAX_OUTPUT_BYTES - kept.len();
+                    truncated |= n > room;
+                    kept.extend_from_slice(&buf[..n.min(room)]);
+                }
+            }
+        }
+        (kept, truncated)
+    })
+}
+
+// 命令行入口：safesecrets run <id> -- <program> [args...]，答案从标准输入读取，
+// 子进程继承当前终端，返回子进程的退出码
+pub fn run_cli(args: &[String]) -> i32 {
+    let empty = String::new();
+    let (id, command) = match args.split_first() {
+        Some((id, rest)) => (id, rest.strip_prefix(&["--".to_string()]).unwrap_or(rest)),
+        None => (&empty, &[][..]),
+    };
+    if id.is_empty() || command.is_empty() {
+        eprintln!("Usage: safesecrets run <id> -- <program> [args...]");
+        return 2;
+    }
+
+    let answer = match read_answer() {
+        Ok(answer) => answer,
+        Err(e) => {
+            eprintln!("Failed to read answer: {}", e);
+            return 1;
+        }
+    };
+
+    match tauri::async_runtime::block_on(verify_security_question(answer.clone())) {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!("Invalid answer");
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+
+    let mut vars = match load_env_vars(id, &answer) {
+        Ok(vars) => vars,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .envs(vars.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .status();
+    scrub(&mut vars);
+
+    match status {
+        Ok(status) => status.code().unwrap_or(1),
+        Err(e) => {
+            eprintln!("Failed to run {}: {}", command[0], e);
+            127
+        }
+    }
+}
+
+// 终端输入时关闭回显，管道输入时按行读取
+fn read_answer() -> std::io::Result<String> {
+    if std::io::stdin().is_terminal() {
+        return rpassword::prompt_password("Answer: ");
+    }
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim_end_matches(['\r', '\n']).to_string())
+}
+
+// 支持 .env 格式和扁平 JSON 对象两种键值形式
+fn load_env_vars(id: &str, answer: &str) -> Result<Vec<(String, String)>, String> {
+    let item = find_entry(id)?;
+    if !item.meta.kind.is_empty() {
+        return Err(format!("Secrets {} is not a key/value secret", id));
+    }
+
+    let mut data = decrypt_with_answer(id, answer)?;
+    let mut text = String::from_utf8_lossy(&data).to_string();
+    data.zeroize();
+
+    let vars = if item.format == ".json" {
+        parse_json_vars(&text)
+    } else {
+        parse_env_vars(&text)
+    };
+    text.zeroize();
+    vars.map_err(|e| format!("Secrets {}: {}", id, e))
+}
+
+fn parse_env_vars(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut canonical = check_format(".env", text).map_err(|e| e.describe())?;
+    let mut vars = vec![];
+    for line in canonical.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            vars.push((key.to_string(), unquote(value).to_string()));
+        }
+    }
+    canonical.zeroize();
+    Ok(vars)
+}
+
+fn parse_json_vars(text: &str) -> Result<Vec<(String, String)>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("Invalid .json: {}", e))?;
+    let serde_json::Value::Object(map) = value else {
+        return Err("expected a JSON object of key/value pairs".to_string());
+    };
+    let mut vars = vec![];
+    for (key, value) in map {
+        let value = match value {
+            serde_json::Value::String(s) => s,
+            serde_json::Value::Number(n) => n.to_string(),
+            serde_json::Value::Bool(b) => b.to_string(),
+            _ => return Err(format!("value of {} is not a scalar", key)),
+        };
+        vars.push((key, value));
+    }
+    Ok(vars)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+// 子进程启动后清空内存中的变量值，zeroize 保证写入不会被编译器优化掉
+fn scrub(vars: &mut Vec<(String, String)>) {
+    for (mut key, mut value) in vars.drain(..) {
+        key.zeroize();
+        value.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_env_vars() {
+        let text = "# comment\n\nexport TOKEN = \"a b\"\nEMPTY=\nQUOTED='x=y'\nRAW=p\"q\n";
+        assert_eq!(
+            parse_env_vars(text).unwrap(),
+            pairs(&[
+                ("TOKEN", "a b"),
+                ("EMPTY", ""),
+                ("QUOTED", "x=y"),
+                ("RAW", "p\"q")
+            ])
+        );
+        assert_eq!(
+            parse_env_vars("A=1\n1B=2").unwrap_err(),
+            "Invalid .env at line 2: invalid variable name \"1B\""
+        );
+        assert!(parse_env_vars("A=\"open").is_err());
+    }
+
+    #[test]
+    fn parses_json_vars() {
+        let mut vars = parse_json_vars(r#"{"TOKEN": "t", "PORT": 8080, "DEBUG": true}"#).unwrap();
+        vars.sort();
+        assert_eq!(
+            vars,
+            pairs(&[("DEBUG", "true"), ("PORT", "8080"), ("TOKEN", "t")])
+        );
+        assert_eq!(
+            parse_json_vars(r#"{"A": {"b": 1}}"#).unwrap_err(),
+            "value of A is not a scalar"
+        );
+        assert_eq!(
+            parse_json_vars("[1]").unwrap_err(),
+            "expected a JSON object of key/value pairs"
+        );
+        assert!(parse_json_vars("{")
+            .unwrap_err()
+            .starts_with("Invalid .json"));
+    }
+
+    #[test]
+    fn unquotes_matching_quotes() {
+        assert_eq!(unquote("\"a b\""), "a b");
+        assert_eq!(unquote("'a'"), "a");
+        assert_eq!(unquote("\"\""), "");
+        assert_eq!(unquote("\"a'"), "\"a'");
+        assert_eq!(unquote("\""), "\"");
+        assert_eq!(unquote("plain"), "plain");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn captures_child_output() {
+        let args = vec![
+            "-c".to_string(),
+            "printf \"$TOKEN\"; echo err >&2".to_string(),
+        ];
+        let result = run_captured("sh", &args, pairs(&[("TOKEN", "s3cret")])).unwrap();
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.stdout, "s3cret");
+        assert_eq!(result.stderr, "err\n");
+        assert!(!result.truncated);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn caps_child_output() {
+        let args = vec![
+            "-c".to_string(),
+            format!("head -c {} /dev/zero; exit 3", MAX_OUTPUT_BYTES * 3),
+        ];
+        let result = run_captured("sh", &args, vec![]).unwrap();
+        assert_eq!(result.exit_code, Some(3));
+        assert_eq!(result.stdout.len(), MAX_OUTPUT_BYTES);
+        assert!(result.truncated);
+    }
+}
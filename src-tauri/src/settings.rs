@@ -0,0 +1,42 @@
+use crate::local_state_dir;
+use crate::mirror::Mirror;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// 本机设置，不随仓库同步
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LocalSettings {
+    pub auto_sync: bool,
+    pub auto_sync_interval_secs: u64,
+    // 远端的新提交需要预览确认后才合并
+    pub review_pulls: bool,
+    pub mirrors: Vec<Mirror>,
+    pub breach_file: Option<String>,
+}
+
+impl Default for LocalSettings {
+    fn default() -> Self {
+        LocalSettings {
+            auto_sync: false,
+            auto_sync_interval_secs: 300,
+            review_pulls: true,
+            mirrors: vec![],
+            breach_file: None,
+        }
+    }
+}
+
+pub fn read_settings() -> LocalSettings {
+    local_state_dir()
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join("settings.json")).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_settings(settings: &LocalSettings) -> Result<(), String> {
+    let data = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(local_state_dir()?.join("settings.json"), data)
+        .map_err(|e| format!("Failed to write settings: {}", e))
+}
@@ -0,0 +1,317 @@
+use crate::entry::{decrypt_typed, Login, TypedSecret};
+use crate::index::ListItem;
+use crate::settings::{read_settings, write_settings};
+use crate::sync::lock_repo;
+use crate::{get_gpg_email, get_index_lines, git_commit, write_index};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use tauri::command;
+
+// 低于该分数的密码在列表中标记为弱密码
+pub const WEAK_SCORE: u8 = 3;
+
+#[derive(Serialize, Debug)]
+pub struct PasswordCheck {
+    score: u8,
+    guesses_log10: f64,
+    crack_time: String,
+    warning: Option<String>,
+    suggestions: Vec<String>,
+    breached: Option<bool>,
+    breach_count: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PasswordFinding {
+    id: String,
+    app: String,
+    desc: String,
+    score: u8,
+    weak: bool,
+    breached: Option<bool>,
+    reused: bool,
+}
+
+#[command]
+pub async fn check_password(
+    password: String,
+    user_inputs: Option<Vec<String>>,
+) -> Result<PasswordCheck, String> {
+    let inputs = user_inputs.unwrap_or_default();
+    let inputs: Vec<&str> = inputs.iter().map(|s| s.as_str()).collect();
+    let entropy = zxcvbn::zxcvbn(&password, &inputs);
+    let breach_count = breach_count(&password)?;
+    let feedback = entropy.feedback();
+    Ok(PasswordCheck {
+        score: u8::from(entropy.score()),
+        guesses_log10: entropy.guesses_log10(),
+        crack_time: entropy
+            .crack_times()
+            .offline_slow_hashing_1e4_per_second()
+            .to_string(),
+        warning: feedback.and_then(|f| f.warning()).map(|w| w.to_string()),
+        suggestions: feedback
+            .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        breached: breach_count.map(|count| count > 0),
+        breach_count,
+    })
+}
+
+#[command]
+pub async fn set_breach_file(path: Option<String>) -> Result<(), String> {
+    if let Some(path) = &path {
+        if !Path::new(path).is_file() {
+            return Err(format!("Breach file not found: {}", path));
+        }
+    }
+    let mut settings = read_settings();
+    settings.breach_file = path;
+    write_settings(&settings)
+}
+
+// 只读地重新评估所有登录条目，不修改索引
+#[command]
+pub async fn password_report(answer: String) -> Result<Vec<PasswordFinding>, String> {
+    let (_, findings) = evaluate_logins(&answer)?;
+    Ok(findings)
+}
+
+// 显式把最新的评估结果写回加密索引，结果没有变化时不提交
+#[command]
+pub async fn update_password_health(answer: String) -> Result<Vec<PasswordFinding>, String> {
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let (updated, findings) = evaluate_logins(&answer)?;
+    if let Some(lines) = updated {
+        write_index(&email, &lines)?;
+        git_commit("update: password health")?;
+    }
+    Ok(findings)
+}
+
+// 返回评估结果，以及分数或泄露标记有变化时更新后的索引
+fn evaluate_logins(answer: &str) -> Result<(Option<Vec<String>>, Vec<PasswordFinding>), String> {
+    let mut lines = get_index_lines()?;
+    let mut logins: Vec<(usize, ListItem, Login)> = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let item = ListItem::parse(line);
+        if item.meta.kind != "login" {
+            continue;
+        }
+        if let TypedSecret::Login(login) = decrypt_typed(&item.id, answer)? {
+            logins.push((i, item, login));
+        }
+    }
+
+    // 只在内存中比较哈希，用于发现重复使用的密码
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (_, _, login) in &logins {
+        if !login.password.is_empty() {
+            *seen.entry(password_sha1(&login.password)).or_default() += 1;
+        }
+    }
+
+    let mut changed = false;
+    let mut findings = vec![];
+    for (i, mut item, login) in logins {
+        let (strength, breached) = assess(&login.password, &[&login.username, &item.app]);
+        // 未配置泄露库时保留上一次的检查结果
+        let breached = breached.or(item.meta.breached);
+        if item.meta.strength != strength || item.meta.breached != breached {
+            item.meta.strength = strength;
+            item.meta.breached = breached;
+            lines[i] = item.to_line();
+            changed = true;
+        }
+        let Some(score) = strength else {
+            continue;
+        };
+        findings.push(PasswordFinding {
+            reused: seen.get(&password_sha1(&login.password)).copied() > Some(1),
+            weak: score < WEAK_SCORE,
+            id: item.id,
+            app: item.app,
+            desc: item.desc,
+            score,
+            breached,
+        });
+    }
+    Ok((changed.then_some(lines), findings))
+}
+
+// 返回强度分数和是否出现在泄露库中，未配置泄露库时后者为 None
+pub fn assess(password: &str, user_inputs: &[&str]) -> (Option<u8>, Option<bool>) {
+    if password.is_empty() {
+        return (None, None);
+    }
+    let score = u8::from(zxcvbn::zxcvbn(password, user_inputs).score());
+    let breached = match breach_count(password) {
+        Ok(count) => count.map(|count| count > 0),
+        Err(e) => {
+            log::warn!("{}", e);
+            None
+        }
+    };
+    (Some(score), breached)
+}
+
+fn breach_count(password: &str) -> Result<Option<u64>, String> {
+    match read_settings().breach_file {
+        Some(path) => lookup_breach(Path::new(&path), &password_sha1(password)).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn password_sha1(password: &str) -> String {
+    hex::encode_upper(Sha1::digest(password.as_bytes()))
+}
+
+// Pwned Passwords 文件每行为 SHA1:COUNT，按哈希排序，直接在文件上二分查找
+fn lookup_breach(path: &Path, hash: &str) -> Result<u64, String> {
+    let file =
+        File::open(path).map_err(|e| format!("Failed to open breach file {:?}: {}", path, e))?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut reader = BufReader::new(file);
+
+    // lo 始终指向某一行的开头
+    let mut lo = 0u64;
+    let mut hi = len;
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let start = line_start_at_or_after(&mut reader, mid)?;
+        if start >= hi {
+            break;
+        }
+        let (line, next) = read_line_at(&mut reader, start)?;
+        match compare_line(&line, hash) {
+            (Ordering::Equal, count) => return Ok(count),
+            (Ordering::Less, _) => hi = start,
+            (Ordering::Greater, _) => lo = next,
+        }
+    }
+
+    // 剩余区间不足一行时顺序扫描
+    let mut pos = lo;
+    while pos < hi {
+        let (line, next) = read_line_at(&mut reader, pos)?;
+        if let (Ordering::Equal, count) = compare_line(&line, hash) {
+            return Ok(count);
+        }
+        if next == pos {
+            break;
+        }
+        pos = next;
+    }
+    Ok(0)
+}
+
+fn line_start_at_or_after(reader: &mut BufReader<File>, pos: u64) -> Result<u64, String> {
+    if pos == 0 {
+        return Ok(0);
+    }
+    let (_, next) = read_line_at(reader, pos - 1)?;
+    Ok(next)
+}
+
+fn read_line_at(reader: &mut BufReader<File>, pos: u64) -> Result<(String, u64), String> {
+    reader
+        .seek(SeekFrom::Start(pos))
+        .map_err(|e| format!("Failed to read breach file: {}", e))?;
+    let mut buf = vec![];
+    let n = reader
+        .read_until(b'\n', &mut buf)
+        .map_err(|e| format!("Failed to read breach file: {}", e))?;
+    Ok((String::from_utf8_lossy(&buf).to_string(), pos + n as u64))
+}
+
+// 返回目标哈希相对该行的大小关系，以及相等时的出现次数
+fn compare_line(line: &str, hash: &str) -> (Ordering, u64) {
+    let line = line.trim();
+    let (line_hash, count) = line.split_once(':').unwrap_or((line, "1"));
+    let ordering = hash.cmp(line_hash.to_ascii_uppercase().as_str());
+    (ordering, count.trim().parse().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn breach_file(name: &str, lines: &[String], newline: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("safesecrets-{}-{}", name, std::process::id()));
+        fs::write(&path, lines.join(newline)).unwrap();
+        path
+    }
+
+    fn sorted_hashes() -> Vec<String> {
+        let mut hashes: Vec<String> = (0..500)
+            .map(|i| password_sha1(&format!("password{}", i)))
+            .collect();
+        hashes.sort();
+        hashes
+    }
+
+    #[test]
+    fn finds_every_line() {
+        let hashes = sorted_hashes();
+        let lines: Vec<String> = hashes
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| format!("{}:{}", hash, i + 1))
+            .collect();
+        for newline in ["\n", "\r\n"] {
+            let path = breach_file("every", &lines, newline);
+            for (i, hash) in hashes.iter().enumerate() {
+                assert_eq!(lookup_breach(&path, hash).unwrap(), i as u64 + 1);
+            }
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn misses_absent_hashes() {
+        let hashes = sorted_hashes();
+        let present: Vec<String> = hashes
+            .iter()
+            .step_by(2)
+            .map(|h| format!("{}:7", h))
+            .collect();
+        let path = breach_file("absent", &present, "\n");
+        for hash in hashes.iter().skip(1).step_by(2) {
+            assert_eq!(lookup_breach(&path, hash).unwrap(), 0);
+        }
+        assert_eq!(lookup_breach(&path, &"0".repeat(40)).unwrap(), 0);
+        assert_eq!(lookup_breach(&path, &"F".repeat(40)).unwrap(), 0);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn handles_small_files() {
+        let hash = password_sha1("password");
+        let path = breach_file(
+            "single",
+            &[format!("{}:3861493", hash.to_lowercase())],
+            "\n",
+        );
+        assert_eq!(lookup_breach(&path, &hash).unwrap(), 3861493);
+        fs::remove_file(path).unwrap();
+
+        let path = breach_file("empty", &[], "\n");
+        assert_eq!(lookup_breach(&path, &hash).unwrap(), 0);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn compares_lines() {
+        assert_eq!(compare_line("ABC:12\r\n", "ABC"), (Ordering::Equal, 12));
+        assert_eq!(compare_line("abd:1", "ABC").0, Ordering::Less);
+        assert_eq!(compare_line("ABB", "ABC"), (Ordering::Greater, 1));
+    }
+}
@@ -0,0 +1,518 @@
+use crate::index::ListItem;
+use crate::migration::check_remote_version;
+use crate::mirror::sync_mirrors;
+use crate::otp::commit_counters;
+use crate::settings::{read_settings, write_settings, LocalSettings};
+use crate::{
+    decrypt_bytes, get_git_cmd, get_index_lines, git_upstream_exists, local_state_dir, now_secs,
+    run_git, split_index_lines,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+const GIT_SSH_COMMAND: &str =
+    "ssh -o StrictHostKeyChecking=accept-new -o UserKnownHostsFile=/dev/null";
+
+const MIN_AUTO_SYNC_INTERVAL_SECS: u64 = 60;
+
+// 同一时间只允许一个任务操作仓库：同步，以及写入文件到提交完成的整个过程
+static SYNC_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    static LOCK_DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+// 同一线程内可以重复获取，写入命令持有锁时提交后的推送仍能进入同步
+pub struct RepoGuard {
+    _guard: Option<MutexGuard<'static, ()>>,
+}
+
+impl Drop for RepoGuard {
+    fn drop(&mut self) {
+        LOCK_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+// 持有期间不能 await，否则锁会跟着任务切换到其他线程
+pub fn lock_repo() -> RepoGuard {
+    let depth = LOCK_DEPTH.with(|depth| depth.get());
+    let guard = if depth == 0 {
+        Some(SYNC_LOCK.lock().unwrap_or_else(|e| e.into_inner()))
+    } else {
+        None
+    };
+    LOCK_DEPTH.with(|d| d.set(depth + 1));
+    RepoGuard { _guard: guard }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SyncState {
+    pub last_sync_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SyncStatus {
+    has_remote: bool,
+    ahead: u32,
+    behind: u32,
+    last_sync_at: Option<u64>,
+    last_error: Option<String>,
+    last_error_at: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SecretsChanged {
+    added: Vec<ListItem>,
+    changed: Vec<ListItem>,
+    removed: Vec<ListItem>,
+}
+
+impl SecretsChanged {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[command]
+pub async fn sync_status() -> Result<SyncStatus, String> {
+    get_sync_status()
+}
+
+#[command]
+pub async fn sync_now() -> Result<SyncStatus, String> {
+    sync_repository()?;
+    get_sync_status()
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RemoteCommit {
+    hash: String,
+    author: String,
+    email: String,
+    date: u64,
+    message: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PullPreview {
+    commit: Option<String>,
+    ahead: u32,
+    behind: u32,
+    changes: SecretsChanged,
+    commits: Vec<RemoteCommit>,
+}
+
+// 只 fetch 不合并，列出远端新增、修改和删除的条目供用户确认
+#[command]
+pub async fn preview_pull() -> Result<PullPreview, String> {
+    let _guard = lock_repo();
+    if !remote_exists()? {
+        return Err("Git repository has not been set yet".to_string());
+    }
+    run_git_remote(&["fetch", "origin"])?;
+    pending_pull()
+}
+
+// 基于最近一次 fetch 的结果生成预览，不访问网络
+fn pending_pull() -> Result<PullPreview, String> {
+    if !ref_exists("refs/remotes/origin/main")? {
+        return Ok(PullPreview {
+            commit: None,
+            ahead: ahead_behind()?.0,
+            behind: 0,
+            changes: SecretsChanged::default(),
+            commits: vec![],
+        });
+    }
+
+    check_remote_version("origin/main")?;
+    let remote = run_git(&["rev-parse", "origin/main"])?.trim().to_string();
+    let (ahead, behind) = ahead_behind()?;
+
+    // 以分叉点为基准，本地未推送的新增不会被当作远端删除
+    let (base_lines, range) = if ref_exists("refs/heads/main")? {
+        let base = run_git(&["merge-base", "main", "origin/main"])
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default();
+        if base.is_empty() {
+            (vec![], remote.clone())
+        } else {
+            (index_lines_at(&base)?, format!("{}..{}", base, remote))
+        }
+    } else {
+        (vec![], remote.clone())
+    };
+    let changes = diff_index(&base_lines, &index_lines_at(&remote)?);
+
+    let log = run_git(&["log", "--format=%H%x1f%an%x1f%ae%x1f%at%x1f%s", &range])?;
+    let commits = log
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\u{1f}').collect();
+            if parts.len() < 5 {
+                return None;
+            }
+            Some(RemoteCommit {
+                hash: parts[0].to_string(),
+                author: parts[1].to_string(),
+                email: parts[2].to_string(),
+                date: parts[3].parse().unwrap_or(0),
+                message: parts[4].to_string(),
+            })
+        })
+        .collect();
+
+    Ok(PullPreview {
+        commit: Some(remote),
+        ahead,
+        behind,
+        changes,
+        commits,
+    })
+}
+
+// 确认后只合并预览时看到的提交，之后远端的新提交留到下次预览
+#[command]
+pub async fn apply_pull(commit: String, accept: bool) -> Result<SyncStatus, String> {
+    if !accept {
+        log::info!("Pull of {} rejected", commit);
+        return get_sync_status();
+    }
+
+    {
+        let _guard = lock_repo();
+        if run_git(&["merge-base", "--is-ancestor", &commit, "origin/main"]).is_err() {
+            return Err(format!("Commit {} is not on origin/main", commit));
+        }
+        let result = commit_counters().and_then(|_| rebase_onto(&commit));
+        record_result(&result)?;
+        result?;
+
+        // 拉取已经完成，推送失败只记录下来，留到下次同步重试；
+        // 推送成功后主仓库已包含全部本地提交，再推送镜像
+        match push_pending() {
+            Ok(()) => sync_mirrors(&read_settings().mirrors),
+            Err(e) => {
+                log::warn!("Push deferred, will retry on next sync: {}", e);
+                record_result(&Err(e))?;
+            }
+        }
+    }
+    get_sync_status()
+}
+
+#[command]
+pub async fn get_auto_sync() -> Result<LocalSettings, String> {
+    Ok(read_settings())
+}
+
+#[command]
+pub async fn set_auto_sync(
+    enabled: bool,
+    interval_secs: u64,
+    review_pulls: Option<bool>,
+) -> Result<(), String> {
+    if interval_secs < MIN_AUTO_SYNC_INTERVAL_SECS {
+        return Err(format!(
+            "Auto sync interval must be at least {} seconds",
+            MIN_AUTO_SYNC_INTERVAL_SECS
+        ));
+    }
+    let mut settings = read_settings();
+    settings.auto_sync = enabled;
+    settings.auto_sync_interval_secs = interval_secs;
+    if let Some(review_pulls) = review_pulls {
+        settings.review_pulls = review_pulls;
+    }
+    write_settings(&settings)
+}
+
+// 后台定时同步，同步后把索引变化通知前端；
+// 需要确认的远端提交只通知一次，由前端预览后调用 apply_pull
+pub async fn auto_sync_loop(app: AppHandle) {
+    let mut offered: Option<String> = None;
+    loop {
+        let settings = read_settings();
+        let interval = if settings.auto_sync {
+            settings
+                .auto_sync_interval_secs
+                .max(MIN_AUTO_SYNC_INTERVAL_SECS)
+        } else {
+            MIN_AUTO_SYNC_INTERVAL_SECS
+        };
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        if !settings_allow_sync() {
+            continue;
+        }
+        let result = tauri::async_runtime::spawn_blocking(|| {
+            let changes = sync_with_changes()?;
+            let _guard = lock_repo();
+            Ok::<_, String>((changes, pending_pull()?))
+        })
+        .await;
+        match result {
+            Ok(Ok((changes, preview))) => {
+                if !changes.is_empty() {
+                    if let Err(e) = app.emit("secrets-changed", changes) {
+                        log::warn!("Failed to emit secrets-changed event: {}", e);
+                    }
+                }
+                if preview.behind > 0 && preview.commit != offered {
+                    offered = preview.commit.clone();
+                    if let Err(e) = app.emit("pull-available", preview) {
+                        log::warn!("Failed to emit pull-available event: {}", e);
+                    }
+                }
+            }
+            Ok(Err(e)) => log::warn!("Auto sync failed: {}", e),
+            Err(e) => log::warn!("Auto sync task failed: {}", e),
+        }
+    }
+}
+
+fn settings_allow_sync() -> bool {
+    let settings = read_settings();
+    settings.auto_sync && (!settings.mirrors.is_empty() || remote_exists().unwrap_or(false))
+}
+
+// 同步并对比同步前后的索引
+pub fn sync_with_changes() -> Result<SecretsChanged, String> {
+    let before = head_commit()?;
+    sync_repository()?;
+    if head_commit()? == before {
+        return Ok(SecretsChanged::default());
+    }
+
+    let old_lines = match &before {
+        Some(rev) => index_lines_at(rev)?,
+        None => vec![],
+    };
+    let new_lines = get_index_lines()?;
+    Ok(diff_index(&old_lines, &new_lines))
+}
+
+fn diff_index(old_lines: &[String], new_lines: &[String]) -> SecretsChanged {
+    let old_items: HashMap<String, ListItem> = old_lines
+        .iter()
+        .map(|line| ListItem::parse(line))
+        .map(|item| (item.id.clone(), item))
+        .collect();
+    let new_items: Vec<ListItem> = new_lines.iter().map(|line| ListItem::parse(line)).collect();
+
+    let mut changes = SecretsChanged::default();
+    for item in &new_items {
+        match old_items.get(&item.id) {
+            None => changes.added.push(item.clone()),
+            Some(old) if old != item => changes.changed.push(item.clone()),
+            _ => {}
+        }
+    }
+    for (id, item) in &old_items {
+        if !new_items.iter().any(|x| &x.id == id) {
+            changes.removed.push(item.clone());
+        }
+    }
+    changes
+}
+
+// 读取某个提交中的索引
+fn index_lines_at(rev: &str) -> Result<Vec<String>, String> {
+    let output = Command::new(get_git_cmd()?)
+        .args(["show", &format!("{}:000.gpg", rev)])
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+    if !output.status.success() {
+        // 该提交中还没有索引文件
+        return Ok(vec![]);
+    }
+    Ok(split_index_lines(&decrypt_bytes(&output.stdout)?))
+}
+
+fn head_commit() -> Result<Option<String>, String> {
+    if !ref_exists("HEAD")? {
+        return Ok(None);
+    }
+    Ok(Some(run_git(&["rev-parse", "HEAD"])?.trim().to_string()))
+}
+
+// 拉取远端变更并推送本地排队的提交，结果写入同步状态
+pub fn sync_repository() -> Result<(), String> {
+    let _guard = lock_repo();
+    let has_remote = remote_exists()?;
+    let mirrors = read_settings().mirrors;
+    if !has_remote && mirrors.is_empty() {
+        return Err("Git repository has not been set yet".to_string());
+    }
+    commit_counters()?;
+    if !has_remote {
+        sync_mirrors(&mirrors);
+        return Ok(());
+    }
+
+    // 等待确认的拉取不算一次成功的同步，也不推送镜像，留到 apply_pull 之后
+    let result = pull_and_push();
+    if let Ok(PullOutcome::PullPending) = result {
+        return Ok(());
+    }
+    let result = result.map(|_| ());
+    record_result(&result)?;
+
+    // 主仓库同步成功后再推送镜像，避免镜像中出现主仓库没有的提交
+    if result.is_ok() {
+        sync_mirrors(&mirrors);
+    }
+    result
+}
+
+enum PullOutcome {
+    Synced,
+    // 远端有新提交等待 preview_pull 预览和 apply_pull 确认，本地提交尚未推送
+    PullPending,
+}
+
+fn record_result(result: &Result<(), String>) -> Result<(), String> {
+    let mut state = read_state();
+    match result {
+        Ok(()) => {
+            state.last_sync_at = Some(now_secs());
+            state.last_error = None;
+            state.last_error_at = None;
+        }
+        Err(e) => {
+            state.last_error = Some(e.clone());
+            state.last_error_at = Some(now_secs());
+        }
+    }
+    write_state(&state)
+}
+
+// 默认远端的新提交要先经 preview_pull 预览、apply_pull 确认后才合并，
+// 这里只推送可以快进的本地提交；关闭 review_pulls 后直接合并
+fn pull_and_push() -> Result<PullOutcome, String> {
+    run_git_remote(&["fetch", "origin"])?;
+    if ref_exists("refs/remotes/origin/main")? {
+        check_remote_version("origin/main")?;
+        if read_settings().review_pulls {
+            if ahead_behind()?.1 > 0 {
+                return Ok(PullOutcome::PullPending);
+            }
+        } else {
+            rebase_onto("origin/main")?;
+        }
+    }
+    push_pending()?;
+    Ok(PullOutcome::Synced)
+}
+
+fn rebase_onto(rev: &str) -> Result<(), String> {
+    if !ref_exists("refs/heads/main")? {
+        run_git(&["checkout", "-B", "main", rev])?;
+    } else if let Err(e) = run_git(&["rebase", rev]) {
+        let _ = run_git(&["rebase", "--abort"]);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn push_pending() -> Result<(), String> {
+    let (ahead, _) = ahead_behind()?;
+    if ahead == 0 {
+        return Ok(());
+    }
+
+    let mut args = vec!["push", "origin", "main"];
+    if !git_upstream_exists()? {
+        args = vec!["push", "-u", "origin", "main"];
+    }
+    run_git_remote(&args)?;
+    Ok(())
+}
+
+fn get_sync_status() -> Result<SyncStatus, String> {
+    let state = read_state();
+    let has_remote = remote_exists()?;
+    let (ahead, behind) = if has_remote { ahead_behind()? } else { (0, 0) };
+    Ok(SyncStatus {
+        has_remote,
+        ahead,
+        behind,
+        last_sync_at: state.last_sync_at,
+        last_error: state.last_error,
+        last_error_at: state.last_error_at,
+    })
+}
+
+// 基于最近一次 fetch 的结果计算本地领先和落后的提交数
+fn ahead_behind() -> Result<(u32, u32), String> {
+    if !ref_exists("refs/heads/main")? {
+        if !ref_exists("refs/remotes/origin/main")? {
+            return Ok((0, 0));
+        }
+        let count = run_git(&["rev-list", "--count", "origin/main"])?;
+        return Ok((0, count.trim().parse().unwrap_or(0)));
+    }
+    if !ref_exists("refs/remotes/origin/main")? {
+        let count = run_git(&["rev-list", "--count", "main"])?;
+        return Ok((count.trim().parse().unwrap_or(0), 0));
+    }
+
+    let output = run_git(&["rev-list", "--left-right", "--count", "main...origin/main"])?;
+    let mut counts = output
+        .split_whitespace()
+        .map(|s| s.parse::<u32>().unwrap_or(0));
+    let ahead = counts.next().unwrap_or(0);
+    let behind = counts.next().unwrap_or(0);
+    Ok((ahead, behind))
+}
+
+fn remote_exists() -> Result<bool, String> {
+    let output = run_git(&["remote"])?;
+    Ok(output.lines().any(|line| line.trim() == "origin"))
+}
+
+fn ref_exists(reference: &str) -> Result<bool, String> {
+    let output = Command::new(get_git_cmd()?)
+        .args(["rev-parse", "--verify", "--quiet", reference])
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+    Ok(output.status.success())
+}
+
+pub fn run_git_remote(args: &[&str]) -> Result<String, String> {
+    let output = Command::new(get_git_cmd()?)
+        .args(args)
+        .env("GIT_SSH_COMMAND", GIT_SSH_COMMAND)
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Error result for git command: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn read_state() -> SyncState {
+    local_state_dir()
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join("sync.json")).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_state(state: &SyncState) -> Result<(), String> {
+    let data = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    fs::write(local_state_dir()?.join("sync.json"), data)
+        .map_err(|e| format!("Failed to write sync state: {}", e))
+}
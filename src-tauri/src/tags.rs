@@ -0,0 +1,142 @@
+use crate::index::{normalize_folder, validate_tag, ListItem};
+use crate::sync::lock_repo;
+use crate::{commit_and_push, get_gpg_email, get_index_lines, write_index};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::command;
+
+#[derive(Serialize, Debug)]
+pub struct TagCount {
+    tag: String,
+    count: usize,
+}
+
+// 标签和文件夹只保存在加密索引中，修改时不需要解密条目内容
+#[command]
+pub async fn set_entry_labels(
+    id: String,
+    tags: Vec<String>,
+    folder: Option<String>,
+    push_to_cloud: String,
+) -> Result<(), String> {
+    let mut tags = tags
+        .iter()
+        .map(|tag| validate_tag(tag))
+        .collect::<Result<Vec<String>, String>>()?;
+    tags.sort();
+    tags.dedup();
+    let folder = match folder {
+        Some(folder) => normalize_folder(&folder)?,
+        None => None,
+    };
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let mut lines = get_index_lines()?;
+    let line = lines
+        .iter_mut()
+        .find(|line| line.starts_with(&format!("{}.", id)))
+        .ok_or_else(|| format!("Secrets {} not found", id))?;
+    let mut item = ListItem::parse(line);
+    item.meta.tags = tags;
+    item.meta.folder = folder;
+    *line = item.to_line();
+
+    write_index(&email, &lines)?;
+    commit_and_push(&format!("update: {}.gpg labels", id), &push_to_cloud)
+}
+
+#[command]
+pub async fn list_tags() -> Result<Vec<TagCount>, String> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for line in get_index_lines()? {
+        for tag in ListItem::parse(&line).meta.tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    Ok(counts
+        .into_iter()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect())
+}
+
+// 返回所有文件夹路径，包含中间层级，便于前端构建目录树
+#[command]
+pub async fn list_folders() -> Result<Vec<String>, String> {
+    let mut folders: Vec<String> = vec![];
+    for line in get_index_lines()? {
+        let Some(folder) = ListItem::parse(&line).meta.folder else {
+            continue;
+        };
+        let mut path = String::new();
+        for part in folder.split('/') {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(part);
+            folders.push(path.clone());
+        }
+    }
+    folders.sort();
+    folders.dedup();
+    Ok(folders)
+}
+
+#[command]
+pub async fn rename_tag(from: String, to: String, push_to_cloud: String) -> Result<usize, String> {
+    let message = format!("update: rename tag {} to {}", from.trim(), to.trim());
+    retag(&[from], &to, &message, &push_to_cloud).await
+}
+
+// 把多个标签合并为一个
+#[command]
+pub async fn merge_tags(
+    sources: Vec<String>,
+    target: String,
+    push_to_cloud: String,
+) -> Result<usize, String> {
+    let message = format!(
+        "update: merge tags {} into {}",
+        sources.join(","),
+        target.trim()
+    );
+    retag(&sources, &target, &message, &push_to_cloud).await
+}
+
+// 把 sources 中的标签替换为 target，返回受影响的条目数
+async fn retag(
+    sources: &[String],
+    target: &str,
+    message: &str,
+    push_to_cloud: &str,
+) -> Result<usize, String> {
+    let target = validate_tag(target)?;
+    let sources: Vec<String> = sources.iter().map(|tag| tag.trim().to_string()).collect();
+
+    let email = get_gpg_email().await?;
+    let _guard = lock_repo();
+    let mut lines = get_index_lines()?;
+    let mut changed = 0;
+    for line in lines.iter_mut() {
+        let mut item = ListItem::parse(line);
+        if !item.meta.tags.iter().any(|tag| sources.contains(tag)) {
+            continue;
+        }
+        for tag in item.meta.tags.iter_mut() {
+            if sources.contains(tag) {
+                *tag = target.clone();
+            }
+        }
+        item.meta.tags.sort();
+        item.meta.tags.dedup();
+        *line = item.to_line();
+        changed += 1;
+    }
+    if changed == 0 {
+        return Ok(0);
+    }
+
+    write_index(&email, &lines)?;
+    commit_and_push(message, push_to_cloud)?;
+    Ok(changed)
+}
@@ -4,12 +4,24 @@ import NotificationManager from '@/components/NotificationManager';
 import Form from '@/components/Form';
 import List from '@/components/List';
 import Panel from '@/components/Panel';
-import { ContextProvider, useCtx } from '@/contexts/Context';
+import { ContextProvider, useCtx, VaultFormat } from '@/contexts/Context';
 import Confirmation from './components/Confirmation';
+import Auth from './components/Auth';
 import { invoke } from '@tauri-apps/api/core';
+import { useState } from 'react';
 
 function AppContent() {
-  const { isInitializing, isGitAvailable, isGpgAvailable } = useCtx();
+  const {
+    isInitializing,
+    isGitAvailable,
+    isGpgAvailable,
+    hasQuestion,
+    vaultFormat,
+    setVaultFormat,
+    setListTrigger,
+  } = useCtx();
+  const [isMigrationDismissed, setIsMigrationDismissed] = useState(false);
+  const [isMigrationAuthOpen, setIsMigrationAuthOpen] = useState(false);
   if (isInitializing) {
     return (
       <div className='app'>
@@ -18,9 +30,43 @@ function AppContent() {
     );
   }
 
-  const { notifications, removeNotification, showError } = useNotification();
+  const { notifications, removeNotification, showError, showSuccess } = useNotification();
 
   const isConfirmationOpen = !isGitAvailable || !isGpgAvailable;
+  const isVaultTooNew = !isConfirmationOpen && vaultFormat?.status === 'too_new';
+  const isMigrationOpen =
+    !isConfirmationOpen &&
+    vaultFormat?.status === 'migrate' &&
+    !isMigrationDismissed &&
+    !isMigrationAuthOpen;
+
+  const openMigrationAuth = () => {
+    if (!hasQuestion) {
+      showError('Please set your GPG Email and Secret Question first');
+      setIsMigrationDismissed(true);
+      return;
+    }
+    setIsMigrationAuthOpen(true);
+  };
+
+  const closeMigrationAuth = () => {
+    setIsMigrationAuthOpen(false);
+    setIsMigrationDismissed(true);
+  };
+
+  const migrateVault = async (answer: string) => {
+    const valid = await invoke<boolean>('verify_security_question', { answer });
+    if (!valid) return false;
+    try {
+      const format = await invoke<VaultFormat>('migrate_vault', { pushToCloud: 'yes', answer });
+      setVaultFormat(format);
+      setListTrigger((prev) => prev + 1);
+      showSuccess('Vault upgraded');
+    } catch (err: any) {
+      showError(err);
+    }
+    return true;
+  };
 
   const exitApp = async () => {
     try {
@@ -59,6 +105,36 @@ function AppContent() {
             `}
           isHtml={true}
         />
+        <Confirmation
+          isOpen={isVaultTooNew}
+          onConfirm={exitApp}
+          title={'Exit Safesecrets'}
+          content={`
+            <p>This vault uses format version <strong>${vaultFormat?.version}</strong>, but this version of Safesecrets only supports up to version ${vaultFormat?.supported}.</p>
+            <p>Please <strong>update Safesecrets</strong> before opening this vault.</p>
+            `}
+          isHtml={true}
+        />
+        <Confirmation
+          isOpen={isMigrationOpen}
+          onClose={() => setIsMigrationDismissed(true)}
+          onConfirm={openMigrationAuth}
+          title={'Upgrade Vault'}
+          content={`
+            <p>This vault uses format version <strong>${vaultFormat?.version}</strong> and needs to be upgraded to version ${vaultFormat?.supported}:</p>
+            <ul>
+            ${(vaultFormat?.pending ?? []).map((step) => `<li>${step}</li>`).join('')}
+            </ul>
+            <p>Each step is committed separately and the vault is restored if any step fails.</p>
+            `}
+          isHtml={true}
+        />
+        <Auth
+          isOpen={isMigrationAuthOpen}
+          onClose={closeMigrationAuth}
+          onVerify={migrateVault}
+          title={'Upgrade Vault'}
+        />
         <div className='side-content'>
           <Form />
         </div>
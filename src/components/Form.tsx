@@ -16,6 +16,34 @@ interface FormData {
   repo: string;
 }
 
+interface ValidationError {
+  format: string;
+  line: number | null;
+  column: number | null;
+  message: string;
+}
+
+// add_secrets 的格式校验错误以 JSON 字符串返回
+const parseValidationError = (error: unknown): ValidationError | null => {
+  if (typeof error !== 'string') {
+    return null;
+  }
+  try {
+    const parsed = JSON.parse(error);
+    return parsed && typeof parsed.format === 'string' && typeof parsed.message === 'string'
+      ? parsed
+      : null;
+  } catch {
+    return null;
+  }
+};
+
+const describeValidationError = (error: ValidationError) => {
+  const column = error.column ? `, column ${error.column}` : '';
+  const line = error.line ? ` (line ${error.line}${column})` : '';
+  return `Invalid ${error.format} format${line}: ${error.message}`;
+};
+
 const Form: React.FC<FormProps> = ({}) => {
   const { showError, showSuccess } = useNotification();
   const { hasEmail, hasQuestion, hasRepository, setHasRepository } = useCtx();
@@ -49,16 +77,16 @@ const Form: React.FC<FormProps> = ({}) => {
   const validateField = (name: keyof FormData, value: string): string | undefined => {
     value = value.trim();
     const size = value.length;
-    const validCharPattern = /^[a-zA-Z0-9-]+$/;
+    const validCharPattern = /^[a-zA-Z0-9.-]+$/;
     switch (name) {
       case 'app':
         if (!validCharPattern.test(value)) {
-          return 'App can only contain letters, numbers, and hyphens (a-z, A-Z, 0-9, -)';
+          return 'App can only contain letters, numbers, dots, and hyphens (a-z, A-Z, 0-9, ., -)';
         }
         return size >= 2 && size <= 12 ? undefined : 'App must be 2-12 characters';
       case 'desc':
         if (!validCharPattern.test(value)) {
-          return 'Description can only contain letters, numbers, and hyphens (a-z, A-Z, 0-9, -)';
+          return 'Description can only contain letters, numbers, dots, and hyphens (a-z, A-Z, 0-9, ., -)';
         }
         return size >= 2 && size <= 24 ? undefined : 'Description must be 2-24 characters';
       case 'secrets':
@@ -181,6 +209,13 @@ const Form: React.FC<FormProps> = ({}) => {
       await invoke('add_secrets', { app, desc, format, secrets, pushToCloud, answer });
       showSuccess('Encrypt secrets successfully');
     } catch (error: any) {
+      const validation = parseValidationError(error);
+      if (validation) {
+        const message = describeValidationError(validation);
+        setErrors((prev) => ({ ...prev, secrets: message }));
+        showError(message);
+        return;
+      }
       showError(error || 'Failed to encrypt secrets');
     } finally {
       setIsEncrypting(false);
@@ -188,69 +223,33 @@ const Form: React.FC<FormProps> = ({}) => {
     }
   };
 
-  const formatJSON = () => {
+  // 由后端按格式校验并规范化内容
+  const handleFormat = async () => {
     try {
-      const parsed = JSON.parse(formData.secrets);
-      const formatted = JSON.stringify(parsed, null, 2);
-      setFormData((prev) => ({
-        ...prev,
-        secrets: formatted,
-      }));
-    } catch (error) {
-      showError('Invalid .json format');
-    }
-  };
-
-  const formatYAML = () => {
-    try {
-      // 简单的YAML格式化实现
-      const lines = formData.secrets.split('\n');
-      const formatted = lines
-        .map((line) => {
-          const trimmed = line.trim();
-          if (!trimmed || trimmed.startsWith('#')) return line;
-
-          // 处理键值对
-          if (trimmed.includes(':')) {
-            const [key, ...valueParts] = trimmed.split(':');
-            const value = valueParts.join(':').trim();
-            const indent = line.length - line.trimStart().length;
-            const spaces = ' '.repeat(indent);
-
-            if (value) {
-              return `${spaces}${key.trim()}: ${value}`;
-            } else {
-              return `${spaces}${key.trim()}:`;
-            }
-          }
-
-          // 处理数组项
-          if (trimmed.startsWith('-')) {
-            const secrets = trimmed.substring(1).trim();
-            const indent = line.length - line.trimStart().length;
-            const spaces = ' '.repeat(indent);
-            return `${spaces}- ${secrets}`;
-          }
-
-          return line;
-        })
-        .join('\n');
-
+      const result = await invoke<{
+        valid: boolean;
+        formatted: string | null;
+        error: ValidationError | null;
+      }>('validate_secrets', {
+        format: formData.format,
+        secrets: formData.secrets,
+        canonicalize: true,
+      });
+      if (!result.valid || result.formatted === null) {
+        showError(
+          result.error
+            ? describeValidationError(result.error)
+            : `Invalid ${formData.format} format`
+        );
+        return;
+      }
+      const formatted = result.formatted;
       setFormData((prev) => ({
         ...prev,
         secrets: formatted,
       }));
-    } catch (error) {
-      showError('Invalid .yaml format');
-    }
-  };
-
-  const handleFormat = () => {
-    if (formData.format === '.yml') {
-      formatYAML();
-    }
-    if (formData.format === '.json') {
-      formatJSON();
+    } catch (error: any) {
+      showError(error);
     }
   };
 
@@ -342,6 +341,9 @@ const Form: React.FC<FormProps> = ({}) => {
                 <option value='.yml'>.yml</option>
                 <option value='.json'>.json</option>
                 <option value='.csv'>.csv</option>
+                <option value='.toml'>.toml</option>
+                <option value='.env'>.env</option>
+                <option value='.pem'>.pem</option>
               </select>
               {errors.format && <span className='error-message'>{errors.format}</span>}
             </div>
@@ -353,7 +355,7 @@ const Form: React.FC<FormProps> = ({}) => {
             </label>
             <div className='input-wrapper'>
               <div className='secrets-container'>
-                {['.yml', '.json'].includes(formData.format) ? (
+                {['.yml', '.json', '.toml', '.env', '.pem'].includes(formData.format) ? (
                   <button
                     type='button'
                     className='format-button'
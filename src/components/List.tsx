@@ -2,6 +2,7 @@ import React, { useState, useEffect } from 'react';
 import './List.css';
 import { useNotification } from '@/contexts/NotificationContext';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import Decryption from './Decryption';
 import { useCtx } from '@/contexts/Context';
 import Auth from './Auth';
@@ -10,11 +11,27 @@ import searchIcon from '@/static/search.png';
 
 interface ListProps {}
 
+interface RemoteCommit {
+  hash: string;
+  author: string;
+  date: number;
+  message: string;
+}
+
+interface PullPreview {
+  commit: string | null;
+  behind: number;
+  changes: { added: ListItem[]; changed: ListItem[]; removed: ListItem[] };
+  commits: RemoteCommit[];
+}
+
 interface ListItem {
   id: string;
   app: string;
   desc: string;
   format: string;
+  strength?: number;
+  breached?: boolean;
 }
 
 const List: React.FC<ListProps> = ({}) => {
@@ -39,8 +56,20 @@ const List: React.FC<ListProps> = ({}) => {
   const [searchStr, setSearchStr] = useState('');
   const [isSearching, setIsSearching] = useState(false);
 
+  const [pullPreview, setPullPreview] = useState<PullPreview | null>(null);
+
   useEffect(() => {
-    loadListItems('', true);
+    loadListItems('', true).then(checkIncoming);
+  }, []);
+
+  useEffect(() => {
+    // 远端有新提交时先预览，用户确认后才合并
+    const unlisten = listen<PullPreview>('pull-available', (event) => {
+      setPullPreview(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   useEffect(() => {
@@ -49,6 +78,58 @@ const List: React.FC<ListProps> = ({}) => {
     }
   }, [listTrigger]);
 
+  useEffect(() => {
+    // 后台同步拉取到变更后刷新列表
+    const unlisten = listen('secrets-changed', () => {
+      loadListItems(searchStr);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [searchStr]);
+
+  const checkIncoming = async () => {
+    try {
+      const status = await invoke<{ behind: number }>('sync_status');
+      if (status.behind > 0) {
+        const preview = await invoke<PullPreview>('preview_pull');
+        if (preview.commit && preview.behind > 0) setPullPreview(preview);
+      }
+    } catch (error: any) {
+      // 未设置远端或离线时不提示
+    }
+  };
+
+  const handlePull = async (accept: boolean) => {
+    const commit = pullPreview?.commit;
+    setPullPreview(null);
+    if (!commit) return;
+    try {
+      await invoke('apply_pull', { commit, accept });
+      if (accept) {
+        await loadListItems(searchStr);
+        showSuccess('Remote changes applied');
+      }
+    } catch (error: any) {
+      showError(error);
+    }
+  };
+
+  const describePull = (preview: PullPreview) => {
+    const name = (item: ListItem) => `${item.id} ${item.app}.${item.desc}${item.format}`;
+    const lines = [`${preview.behind} incoming commit(s)`, ''];
+    preview.changes.added.forEach((item) => lines.push(`+ ${name(item)}`));
+    preview.changes.changed.forEach((item) => lines.push(`~ ${name(item)}`));
+    preview.changes.removed.forEach((item) => lines.push(`- ${name(item)}`));
+    lines.push('');
+    preview.commits.forEach((c) =>
+      lines.push(
+        `${c.hash.slice(0, 7)} ${c.author} ${new Date(c.date * 1000).toLocaleString()} ${c.message}`
+      )
+    );
+    return lines.join('\n');
+  };
+
   const handleDelete = async (id: string) => {
     try {
       closeConfirmation();
@@ -228,6 +309,16 @@ const List: React.FC<ListProps> = ({}) => {
               </div>
               <div className={'list-item-field list-item-format'} style={{ flex: 10 }}>
                 {item.format}
+                {item.breached && (
+                  <span className='list-item-flag' title='Found in breached password list'>
+                    breached
+                  </span>
+                )}
+                {!item.breached && item.strength !== undefined && item.strength < 3 && (
+                  <span className='list-item-flag' title='Weak password'>
+                    weak
+                  </span>
+                )}
               </div>
               <div style={{ textAlign: 'right', flex: 20, display: 'flex', gap: 6 }}>
                 <button
@@ -277,6 +368,14 @@ const List: React.FC<ListProps> = ({}) => {
         content={`Are you sure to delete the secrets <strong>${id}</strong> ?`}
         isHtml={true}
       />
+
+      <Confirmation
+        isOpen={pullPreview !== null}
+        onClose={() => handlePull(false)}
+        onConfirm={() => handlePull(true)}
+        title={'Apply Remote Changes'}
+        content={pullPreview ? describePull(pullPreview) : ''}
+      />
     </>
   );
 };
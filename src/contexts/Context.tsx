@@ -17,6 +17,15 @@ interface ContextType {
   isGitAvailable: boolean;
   isGpgAvailable: boolean;
   isInitializing: boolean;
+  vaultFormat: VaultFormat | null;
+  setVaultFormat: (vaultFormat: VaultFormat | null) => void;
+}
+
+export interface VaultFormat {
+  version: number;
+  supported: number;
+  status: 'ok' | 'migrate' | 'too_new';
+  pending: string[];
 }
 
 const Context = createContext<ContextType | undefined>(undefined);
@@ -53,6 +62,7 @@ export const ContextProvider: React.FC<ContextProviderProps> = ({ children }) =>
   const [isGitAvailable, setIsGitAvailable] = useState(false);
   const [isGpgAvailable, setIsGpgAvailable] = useState(false);
   const [isInitializing, setIsInitializing] = useState(true);
+  const [vaultFormat, setVaultFormat] = useState<VaultFormat | null>(null);
 
   const loadCtx = async () => {
     setIsInitializing(true);
@@ -62,6 +72,7 @@ export const ContextProvider: React.FC<ContextProviderProps> = ({ children }) =>
       invoke<boolean>('is_git_available'),
       invoke<boolean>('is_gpg_available'),
       invoke<boolean>('git_repository_exists'),
+      invoke<VaultFormat>('vault_format'),
     ]);
     if (results[0].status === 'fulfilled') {
       setEmail(maskEmail(results[0].value));
@@ -74,6 +85,7 @@ export const ContextProvider: React.FC<ContextProviderProps> = ({ children }) =>
     if (results[2].status === 'fulfilled') setIsGitAvailable(results[2].value);
     if (results[3].status === 'fulfilled') setIsGpgAvailable(results[3].value);
     if (results[4].status === 'fulfilled') setHasRepository(results[4].value);
+    if (results[5].status === 'fulfilled') setVaultFormat(results[5].value);
     setIsInitializing(false);
   };
 
@@ -97,6 +109,8 @@ export const ContextProvider: React.FC<ContextProviderProps> = ({ children }) =>
     hasRepository,
     setHasRepository,
     isInitializing,
+    vaultFormat,
+    setVaultFormat,
   };
 
   return <Context.Provider value={value}>{children}</Context.Provider>;